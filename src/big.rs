@@ -1,16 +1,33 @@
-use std::fmt::{Display, Formatter};
-use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign};
 use std::str::FromStr;
+use crate::comb;
 use crate::complex::Complex;
 use crate::ft::RevCash;
-use crate::oper::{add_vec, mul_vec, sqr};
+use crate::float::{round_digits, RoundingMode};
+use crate::oper::{add_assign_vec, add_small_vec, add_vec, cmp_vec, divmod_vec, isqrt_vec, mul_vec, shl10, sqr, sub_assign_vec, sub_vec, trim_vec};
 
 // Положительные целые большие числа
 // Самая младшая цифра числа в массиве идет первой (порядок big endian)
+#[derive(Clone, PartialEq, Eq)]
 pub struct BigDecimal {
-    pub digits: Vec<u8>
+    pub(crate) digits: Vec<u8>
 }
 
+// Ошибка разбора строки
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBigError;
+
+impl Display for ParseBigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid integer literal")
+    }
+}
+
+impl std::error::Error for ParseBigError {}
+
 impl BigDecimal {
     pub fn zero() -> Self {
         Self { digits: vec![0u8] }
@@ -19,11 +36,87 @@ impl BigDecimal {
         Self { digits: vec![1u8] }
     }
     pub fn sqr(&self) -> Self { Self { digits: sqr(&self.digits) } }
+    pub fn is_zero(&self) -> bool { self.digits == [0u8] }
+    // число по десятичным цифрам, младшая первая; каждая цифра должна быть от 0 до 9
+    pub fn from_digits(digits: &[u8]) -> Self {
+        if let Some(d) = digits.iter().find(|&&d| d > 9) { panic!("invalid decimal digit {d}"); }
+        let mut digits = digits.to_vec();
+        trim_vec(&mut digits);
+        Self { digits }
+    }
+    // десятичные цифры, младшая первая (поле закрыто, чтобы число нельзя было собрать без проверки)
+    pub fn digits(&self) -> &[u8] { &self.digits }
+    // целая часть квадратного корня
+    pub fn sqrt(&self) -> Self { Self { digits: isqrt_vec(&self.digits) } }
+    // прибавить к числу k на месте (счетчики, накопление малых слагаемых)
+    pub fn add_small(&mut self, k: u64) { add_small_vec(&mut self.digits, k) }
+    // частное и остаток
+    pub fn divmod(&self, rhs: &Self) -> (Self, Self) {
+        let (q, r) = divmod_vec(&self.digits, &rhs.digits);
+        (Self { digits: q }, Self { digits: r })
+    }
+    // десятичная запись числа в конец буфера, место резервируется сразу
+    pub fn write_to(&self, buf: &mut String) {
+        buf.reserve(self.digits.len());
+        buf.extend(self.digits.iter().rev().map(|&d| (b'0' + d) as char));
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Десятичные сдвиги и доступ к цифрам: число хранится по основанию 10,
+// поэтому умножение и деление на 10^k - сдвиг вектора цифр, без умножения
+
+impl BigDecimal {
+    // x * 10^k
+    pub fn shl10(&self, k: usize) -> Self { Self { digits: shl10(&self.digits, k) } }
+    // x / 10^k с округлением mode (RoundingMode::Down - отбрасывание цифр)
+    pub fn shr10(&self, k: usize, mode: RoundingMode) -> Self {
+        Self { digits: round_digits(&self.digits, k, false, mode) }
+    }
+    // наибольшее k, при котором x делится на 10^k, у нуля не определено
+    pub fn trailing_decimal_zeros(&self) -> Option<usize> {
+        if self.is_zero() { return None }
+        self.digits.iter().position(|&d| d != 0)
+    }
+    // число десятичных цифр, у нуля одна цифра
+    pub fn num_digits(&self) -> usize { self.digits.len() }
+    // цифра при 10^i, за старшей цифрой - нули
+    pub fn digit(&self, i: usize) -> u8 { self.digits.get(i).copied().unwrap_or(0) }
+    // k старших цифр, остальные заменяются нулями
+    pub fn truncate_to_significant(&self, k: usize) -> Self {
+        let n = self.digits.len();
+        if k >= n { return self.clone() }
+        if k == 0 { return Self::zero() }
+        let mut digits = self.digits.clone();
+        digits[..n - k].fill(0);
+        Self { digits }
+    }
 }
 
+// Строка собирается целиком и выводится одним вызовом (с учетом ширины и заполнения)
 impl Display for BigDecimal {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        self.digits.iter().rev().map(|d| write!(f, "{d}")).collect()
+        let mut s = String::new();
+        self.write_to(&mut s);
+        f.pad_integral(true, "", &s)
+    }
+}
+
+impl Debug for BigDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl PartialOrd for BigDecimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigDecimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        cmp_vec(&self.digits, &other.digits)
     }
 }
 
@@ -33,53 +126,197 @@ impl From<&str> for BigDecimal {
     fn from(value: &str) -> Self {
         match value.find("*") {
             Some(index) => parse_str(value, index),
-            None => Self { digits: to_vec(value) }
+            None => value.parse().unwrap_or_else(|e| panic!("{e}: {value}"))
         }
     }
 }
 
-// Преобразование BigDecimal::from("<&[u8]>")
-impl From<&[u8]> for BigDecimal {
-    fn from(value: &[u8]) -> Self {
-        Self { digits: value.to_vec() }
+// Разбор строки "[+]<цифры>", префиксы 0x, 0b, 0o задают основание 16, 2, 8
+impl FromStr for BigDecimal {
+    type Err = ParseBigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix('+').unwrap_or(s);
+        let prefixes = [("0x", 16), ("0X", 16), ("0b", 2), ("0B", 2), ("0o", 8), ("0O", 8)];
+        for (prefix, radix) in prefixes {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                if rest.starts_with('+') { return Err(ParseBigError) }
+                return Self::from_str_radix(rest, radix)
+            }
+        }
+        if s.is_empty() || !s.bytes().all(|x| x.is_ascii_digit()) { return Err(ParseBigError) }
+        Ok(Self { digits: to_vec(s) })
     }
 }
 
 // a + b
+// сложение на месте: результат пишется в цифры операнда, переданного по значению
 impl Add for BigDecimal {
     type Output = BigDecimal;
-    fn add(self, rhs: Self) -> Self::Output {
-        Self { digits: add_vec(&self.digits, &rhs.digits) }
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self += &rhs;
+        self
+    }
+}
+
+impl Add<&BigDecimal> for BigDecimal {
+    type Output = BigDecimal;
+    fn add(mut self, rhs: &BigDecimal) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl Add<BigDecimal> for &BigDecimal {
+    type Output = BigDecimal;
+    fn add(self, mut rhs: BigDecimal) -> Self::Output {
+        rhs += self;
+        rhs
+    }
+}
+
+impl Add<&BigDecimal> for &BigDecimal {
+    type Output = BigDecimal;
+    fn add(self, rhs: &BigDecimal) -> Self::Output {
+        BigDecimal { digits: add_vec(&self.digits, &rhs.digits) }
     }
 }
 
 // a += b
 impl AddAssign for BigDecimal {
     fn add_assign(&mut self, rhs: Self) {
-        self.digits = add_vec(&self.digits, &rhs.digits);
+        add_assign_vec(&mut self.digits, &rhs.digits);
+    }
+}
+
+impl AddAssign<&BigDecimal> for BigDecimal {
+    fn add_assign(&mut self, rhs: &BigDecimal) {
+        add_assign_vec(&mut self.digits, &rhs.digits);
+    }
+}
+
+// a - b
+// требование: a >= b
+impl Sub for BigDecimal {
+    type Output = BigDecimal;
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self -= &rhs;
+        self
+    }
+}
+
+impl Sub<&BigDecimal> for BigDecimal {
+    type Output = BigDecimal;
+    fn sub(mut self, rhs: &BigDecimal) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl Sub<BigDecimal> for &BigDecimal {
+    type Output = BigDecimal;
+    fn sub(self, rhs: BigDecimal) -> Self::Output {
+        self - &rhs
     }
 }
 
-// a * b
-impl Mul for BigDecimal {
+impl Sub<&BigDecimal> for &BigDecimal {
     type Output = BigDecimal;
-    fn mul(self, rhs: Self) -> Self::Output {
-        Self { digits: mul_vec(&self.digits, &rhs.digits) }
+    fn sub(self, rhs: &BigDecimal) -> Self::Output {
+        BigDecimal { digits: sub_vec(&self.digits, &rhs.digits) }
+    }
+}
+
+impl SubAssign for BigDecimal {
+    fn sub_assign(&mut self, rhs: Self) {
+        sub_assign_vec(&mut self.digits, &rhs.digits);
+    }
+}
+
+impl SubAssign<&BigDecimal> for BigDecimal {
+    fn sub_assign(&mut self, rhs: &BigDecimal) {
+        sub_assign_vec(&mut self.digits, &rhs.digits);
+    }
+}
+
+// a * b, a / b, a % b - результат всегда в новом векторе,
+// поэтому все варианты со ссылками сводятся к &a op &b
+macro_rules! ref_ops {
+    ($($op:ident, $f:ident, $op_assign:ident, $f_assign:ident, $vec:ident);*) => {$(
+        impl $op<&BigDecimal> for &BigDecimal {
+            type Output = BigDecimal;
+            fn $f(self, rhs: &BigDecimal) -> Self::Output {
+                BigDecimal { digits: $vec(&self.digits, &rhs.digits) }
+            }
+        }
+
+        impl $op for BigDecimal {
+            type Output = BigDecimal;
+            fn $f(self, rhs: Self) -> Self::Output { (&self).$f(&rhs) }
+        }
+
+        impl $op<&BigDecimal> for BigDecimal {
+            type Output = BigDecimal;
+            fn $f(self, rhs: &BigDecimal) -> Self::Output { (&self).$f(rhs) }
+        }
+
+        impl $op<BigDecimal> for &BigDecimal {
+            type Output = BigDecimal;
+            fn $f(self, rhs: BigDecimal) -> Self::Output { self.$f(&rhs) }
+        }
+
+        impl $op_assign for BigDecimal {
+            fn $f_assign(&mut self, rhs: Self) { self.digits = $vec(&self.digits, &rhs.digits); }
+        }
+
+        impl $op_assign<&BigDecimal> for BigDecimal {
+            fn $f_assign(&mut self, rhs: &BigDecimal) { self.digits = $vec(&self.digits, &rhs.digits); }
+        }
+    )*}
+}
+
+ref_ops!(
+    Mul, mul, MulAssign, mul_assign, mul_vec;
+    Div, div, DivAssign, div_assign, div_vec;
+    Rem, rem, RemAssign, rem_assign, rem_vec
+);
+
+fn div_vec(a: &[u8], b: &[u8]) -> Vec<u8> { divmod_vec(a, b).0 }
+
+fn rem_vec(a: &[u8], b: &[u8]) -> Vec<u8> { divmod_vec(a, b).1 }
+
+// Произведение и сумма последовательности (попарно, деревом)
+impl Product for BigDecimal {
+    fn product<I: Iterator<Item = BigDecimal>>(iter: I) -> Self {
+        comb::product(iter)
+    }
+}
+
+impl<'a> Product<&'a BigDecimal> for BigDecimal {
+    fn product<I: Iterator<Item = &'a BigDecimal>>(iter: I) -> Self {
+        comb::product(iter.cloned())
+    }
+}
+
+impl Sum for BigDecimal {
+    fn sum<I: Iterator<Item = BigDecimal>>(iter: I) -> Self {
+        comb::sum(iter)
     }
 }
 
-impl MulAssign for BigDecimal {
-    fn mul_assign(&mut self, rhs: Self) {
-        self.digits = mul_vec(&self.digits, &rhs.digits);
+impl<'a> Sum<&'a BigDecimal> for BigDecimal {
+    fn sum<I: Iterator<Item = &'a BigDecimal>>(iter: I) -> Self {
+        comb::sum(iter.cloned())
     }
 }
 
 // Преобразовать строку в вектор
 fn to_vec(s: &str) -> Vec<u8> {
-    s.as_bytes()
+    let mut v = s.as_bytes()
         .into_iter()
         .rev().map(|x| *x - 48)
-        .collect()
+        .collect();
+    trim_vec(&mut v);
+    v
 }
 
 // Максимальная длина подстроки из 0
@@ -112,3 +349,63 @@ fn parse_str(s: &str, index: usize) -> BigDecimal {
         base.repeat(rep as usize).as_str()
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    #[test]
+    fn shr10_rounding() {
+        // x / 10^k в порядке HalfEven, HalfUp, Down, Up, Ceiling, Floor
+        let modes = [
+            RoundingMode::HalfEven, RoundingMode::HalfUp, RoundingMode::Down,
+            RoundingMode::Up, RoundingMode::Ceiling, RoundingMode::Floor
+        ];
+        let cases = [
+            ("12345", 1, ["1234", "1235", "1234", "1235", "1235", "1234"]),
+            ("12355", 1, ["1236", "1236", "1235", "1236", "1236", "1235"]),
+            ("12351", 2, ["124", "124", "123", "124", "124", "123"]),
+            ("12349", 2, ["123", "123", "123", "124", "124", "123"]),
+            ("12300", 2, ["123", "123", "123", "123", "123", "123"]),
+            ("99950", 2, ["1000", "1000", "999", "1000", "1000", "999"]),
+            ("5", 1, ["0", "1", "0", "1", "1", "0"]),
+            ("12345", 5, ["0", "0", "0", "1", "1", "0"]),
+            ("12345", 9, ["0", "0", "0", "1", "1", "0"]),
+            ("0", 3, ["0", "0", "0", "0", "0", "0"])
+        ];
+        for (x, k, expected) in cases {
+            for (mode, e) in modes.into_iter().zip(expected) {
+                assert_eq!(big(x).shr10(k, mode), big(e), "{x} / 10^{k}, {mode:?}");
+            }
+        }
+        assert_eq!(big("12345").shr10(0, RoundingMode::Up), big("12345"));
+        assert_eq!(big("12345").shl10(3).shr10(3, RoundingMode::Up), big("12345"));
+        assert_eq!(BigDecimal::zero().shl10(3), BigDecimal::zero());
+    }
+
+    #[test]
+    fn truncate_to_significant() {
+        let x = big("9876543210");
+        assert_eq!(x.truncate_to_significant(0), BigDecimal::zero());
+        assert_eq!(x.truncate_to_significant(1), big("9000000000"));
+        assert_eq!(x.truncate_to_significant(4), big("9876000000"));
+        assert_eq!(x.truncate_to_significant(10), x);
+        assert_eq!(x.truncate_to_significant(20), x);
+        assert_eq!(BigDecimal::zero().truncate_to_significant(0), BigDecimal::zero());
+        assert_eq!(BigDecimal::zero().truncate_to_significant(3), BigDecimal::zero());
+        // нули после отбрасывания не портят представление: ноль - одна цифра
+        assert_eq!(x.truncate_to_significant(0).digits(), &[0u8]);
+    }
+
+    #[test]
+    fn digits_access() {
+        let x = big("1203000");
+        assert_eq!(x.num_digits(), 7);
+        assert_eq!(x.trailing_decimal_zeros(), Some(3));
+        assert_eq!(BigDecimal::zero().trailing_decimal_zeros(), None);
+        assert_eq!(BigDecimal::zero().num_digits(), 1);
+        assert_eq!((x.digit(6), x.digit(4), x.digit(3), x.digit(7), x.digit(100)), (1, 0, 3, 0, 0));
+    }
+}
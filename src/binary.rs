@@ -0,0 +1,620 @@
+use std::cmp::Ordering;
+use std::fmt::{Binary, Debug, Display, Formatter, LowerHex};
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Neg, Not, Shl, ShlAssign, Shr, ShrAssign};
+use crate::big::BigDecimal;
+
+//-------------------------------------------------------------------------------------------------
+// Большие числа в двоичном виде: 64-битные слова, младшее первое
+// битовые операции над десятичными цифрами невозможны, поэтому для них отдельный тип,
+// перевод в BigDecimal и обратно - через запись в системе счисления с основанием 256
+// старших нулевых слов нет, у нуля слов нет вообще
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BigBinary {
+    limbs: Vec<u64>
+}
+
+impl BigBinary {
+    pub fn zero() -> Self { Self { limbs: Vec::new() } }
+    pub fn one() -> Self { Self { limbs: vec![1] } }
+    pub fn is_zero(&self) -> bool { self.limbs.is_empty() }
+    // число по словам, младшее первое
+    pub fn from_limbs(limbs: &[u64]) -> Self {
+        let mut limbs = limbs.to_vec();
+        trim(&mut limbs);
+        Self { limbs }
+    }
+    pub fn limbs(&self) -> &[u64] { &self.limbs }
+
+    // i-й бит
+    pub fn bit(&self, i: u64) -> bool {
+        let k = (i / 64) as usize;
+        k < self.limbs.len() && (self.limbs[k] >> (i % 64)) & 1 == 1
+    }
+    // установить i-й бит в value
+    pub fn set_bit(&mut self, i: u64, value: bool) {
+        let k = (i / 64) as usize;
+        if value {
+            if k >= self.limbs.len() { self.limbs.resize(k + 1, 0); }
+            self.limbs[k] |= 1 << (i % 64);
+        } else if k < self.limbs.len() {
+            self.limbs[k] &= !(1 << (i % 64));
+            trim(&mut self.limbs);
+        }
+    }
+    // число значащих битов, у нуля 0
+    pub fn bits(&self) -> u64 {
+        match self.limbs.last() {
+            Some(x) => self.limbs.len() as u64 * 64 - x.leading_zeros() as u64,
+            None => 0
+        }
+    }
+    // число младших нулевых битов, у нуля не определено
+    pub fn trailing_zeros(&self) -> Option<u64> {
+        let k = self.limbs.iter().position(|&x| x != 0)?;
+        Some(k as u64 * 64 + self.limbs[k].trailing_zeros() as u64)
+    }
+    // число единичных битов
+    pub fn count_ones(&self) -> u64 {
+        self.limbs.iter().map(|x| x.count_ones() as u64).sum()
+    }
+}
+
+// Убрать старшие нулевые слова
+fn trim(v: &mut Vec<u64>) {
+    while v.last() == Some(&0) { v.pop(); }
+}
+
+// v += 1
+fn inc(v: &mut Vec<u64>) {
+    for x in v.iter_mut() {
+        *x = x.wrapping_add(1);
+        if *x != 0 { return }
+    }
+    v.push(1);
+}
+
+// v -= 1, v > 0
+fn dec(v: &mut Vec<u64>) {
+    for x in v.iter_mut() {
+        *x = x.wrapping_sub(1);
+        if *x != u64::MAX { break }
+    }
+    trim(v);
+}
+
+// x << k
+fn shl_vec(v: &[u64], k: u64) -> Vec<u64> {
+    if v.is_empty() { return Vec::new() }
+    let (s, b) = ((k / 64) as usize, k % 64);
+    let mut res = vec![0u64; s];
+    res.reserve(v.len() + 1);
+    if b == 0 {
+        res.extend_from_slice(v);
+        return res
+    }
+    let mut carry = 0;
+    for &x in v {
+        res.push((x << b) | carry);
+        carry = x >> (64 - b);
+    }
+    res.push(carry);
+    trim(&mut res);
+    res
+}
+
+// x >> k
+fn shr_vec(v: &[u64], k: u64) -> Vec<u64> {
+    let s = (k / 64) as usize;
+    if s >= v.len() { return Vec::new() }
+    let b = k % 64;
+    let v = &v[s..];
+    let mut res: Vec<u64> = if b == 0 {
+        v.to_vec()
+    } else {
+        (0..v.len()).map(|i| (v[i] >> b) | v.get(i + 1).map_or(0, |x| x << (64 - b))).collect()
+    };
+    trim(&mut res);
+    res
+}
+
+// Поразрядная операция над словами, более короткий операнд дополняется нулями
+fn zip_vec<F: Fn(u64, u64) -> u64>(a: &[u64], b: &[u64], f: F) -> Vec<u64> {
+    let n = a.len().max(b.len());
+    let mut res: Vec<u64> = (0..n).map(|i| f(a.get(i).copied().unwrap_or(0), b.get(i).copied().unwrap_or(0))).collect();
+    trim(&mut res);
+    res
+}
+
+impl From<u128> for BigBinary {
+    fn from(value: u128) -> Self {
+        Self::from_limbs(&[value as u64, (value >> 64) as u64])
+    }
+}
+
+impl From<&BigDecimal> for BigBinary {
+    fn from(value: &BigDecimal) -> Self {
+        Self { limbs: value.to_u64_digits() }
+    }
+}
+
+impl From<&BigBinary> for BigDecimal {
+    fn from(value: &BigBinary) -> Self {
+        let bytes: Vec<u8> = value.limbs.iter().flat_map(|x| x.to_le_bytes()).collect();
+        BigDecimal::from_bytes_le(&bytes)
+    }
+}
+
+impl Display for BigBinary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&BigDecimal::from(self), f)
+    }
+}
+
+impl Debug for BigBinary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+// {:x} и {:b} - прямо по словам, без перевода в десятичную систему
+impl LowerHex for BigBinary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self.limbs.split_last() {
+            Some((hi, rest)) => {
+                let mut s = format!("{hi:x}");
+                for x in rest.iter().rev() { s.push_str(&format!("{x:016x}")); }
+                s
+            }
+            None => "0".to_string()
+        };
+        f.pad_integral(true, "0x", &s)
+    }
+}
+
+impl Binary for BigBinary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self.limbs.split_last() {
+            Some((hi, rest)) => {
+                let mut s = format!("{hi:b}");
+                for x in rest.iter().rev() { s.push_str(&format!("{x:064b}")); }
+                s
+            }
+            None => "0".to_string()
+        };
+        f.pad_integral(true, "0b", &s)
+    }
+}
+
+impl PartialOrd for BigBinary {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigBinary {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs.len().cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Числа со знаком: знак и модуль, а поразрядные операции - как над бесконечным
+// дополнительным кодом (отрицательное число слева дополнено единицами), как у i64
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SignedBinary {
+    negative: bool,
+    magnitude: BigBinary
+}
+
+impl SignedBinary {
+    pub fn zero() -> Self { Self { negative: false, magnitude: BigBinary::zero() } }
+    // (-1)^negative * magnitude, у нуля знака нет
+    pub fn new(negative: bool, magnitude: BigBinary) -> Self {
+        let negative = negative && !magnitude.is_zero();
+        Self { negative, magnitude }
+    }
+    pub fn is_zero(&self) -> bool { self.magnitude.is_zero() }
+    pub fn is_negative(&self) -> bool { self.negative }
+    pub fn magnitude(&self) -> &BigBinary { &self.magnitude }
+
+    // i-й бит дополнительного кода: -m = !(m - 1)
+    pub fn bit(&self, i: u64) -> bool {
+        if !self.negative { return self.magnitude.bit(i) }
+        let mut m = self.magnitude.limbs.clone();
+        dec(&mut m);
+        !BigBinary { limbs: m }.bit(i)
+    }
+    // установить i-й бит дополнительного кода в value
+    pub fn set_bit(&mut self, i: u64, value: bool) {
+        let mut mask = BigBinary::zero();
+        mask.set_bit(i, true);
+        let mask = SignedBinary::from(mask);
+        *self = if value { &*self | &mask } else { &*self & &!mask };
+    }
+    // число значащих битов модуля
+    pub fn bits(&self) -> u64 { self.magnitude.bits() }
+    // младшие нулевые биты у x и -x совпадают
+    pub fn trailing_zeros(&self) -> Option<u64> { self.magnitude.trailing_zeros() }
+    // число единичных битов дополнительного кода; у отрицательного их бесконечно много - None
+    // (единицы модуля - magnitude().count_ones())
+    pub fn count_ones(&self) -> Option<u64> {
+        if self.negative { None } else { Some(self.magnitude.count_ones()) }
+    }
+}
+
+// Дополнительный код из n слов
+fn twos(x: &SignedBinary, n: usize) -> Vec<u64> {
+    let mut v = x.magnitude.limbs.clone();
+    v.resize(n, 0);
+    if x.negative { negate(&mut v); }
+    v
+}
+
+// -v в дополнительном коде той же длины
+fn negate(v: &mut [u64]) {
+    let mut carry = true;
+    for x in v.iter_mut() {
+        let (y, c) = (!*x).overflowing_add(carry as u64);
+        *x = y;
+        carry = c;
+    }
+}
+
+// Поразрядная операция: f над словами дополнительного кода, знак результата - f над знаками
+fn bitwise<F: Fn(u64, u64) -> u64>(a: &SignedBinary, b: &SignedBinary, f: F) -> SignedBinary {
+    // на одно слово больше, чтобы в старшем слове был только знак
+    let n = a.magnitude.limbs.len().max(b.magnitude.limbs.len()) + 1;
+    let (x, y) = (twos(a, n), twos(b, n));
+    let mut v: Vec<u64> = x.iter().zip(&y).map(|(&x, &y)| f(x, y)).collect();
+    let negative = f(a.negative as u64, b.negative as u64) & 1 == 1;
+    if negative { negate(&mut v); }
+    trim(&mut v);
+    SignedBinary::new(negative, BigBinary { limbs: v })
+}
+
+impl From<BigBinary> for SignedBinary {
+    fn from(value: BigBinary) -> Self {
+        Self { negative: false, magnitude: value }
+    }
+}
+
+impl From<i128> for SignedBinary {
+    fn from(value: i128) -> Self {
+        Self::new(value < 0, BigBinary::from(value.unsigned_abs()))
+    }
+}
+
+impl Display for SignedBinary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad_integral(!self.negative, "", &BigDecimal::from(&self.magnitude).to_string())
+    }
+}
+
+impl Debug for SignedBinary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl PartialOrd for SignedBinary {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SignedBinary {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => self.magnitude.cmp(&other.magnitude),
+            (true, true) => other.magnitude.cmp(&self.magnitude)
+        }
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Операторы
+
+// -x
+impl Neg for SignedBinary {
+    type Output = SignedBinary;
+    fn neg(self) -> Self::Output {
+        Self::new(!self.negative, self.magnitude)
+    }
+}
+
+impl Neg for &SignedBinary {
+    type Output = SignedBinary;
+    fn neg(self) -> Self::Output { -self.clone() }
+}
+
+// !x = -x - 1
+impl Not for SignedBinary {
+    type Output = SignedBinary;
+    fn not(self) -> Self::Output { !&self }
+}
+
+impl Not for &SignedBinary {
+    type Output = SignedBinary;
+    fn not(self) -> Self::Output {
+        let mut m = self.magnitude.limbs.clone();
+        if self.negative { dec(&mut m) } else { inc(&mut m) }
+        SignedBinary::new(!self.negative, BigBinary { limbs: m })
+    }
+}
+
+// x << k, x >> k
+impl Shl<u64> for &BigBinary {
+    type Output = BigBinary;
+    fn shl(self, rhs: u64) -> Self::Output { BigBinary { limbs: shl_vec(&self.limbs, rhs) } }
+}
+
+impl Shr<u64> for &BigBinary {
+    type Output = BigBinary;
+    fn shr(self, rhs: u64) -> Self::Output { BigBinary { limbs: shr_vec(&self.limbs, rhs) } }
+}
+
+impl Shl<u64> for &SignedBinary {
+    type Output = SignedBinary;
+    fn shl(self, rhs: u64) -> Self::Output { SignedBinary::new(self.negative, &self.magnitude << rhs) }
+}
+
+// сдвиг вправо с округлением вниз: для x < 0 x >> k = -((|x| - 1) >> k) - 1
+impl Shr<u64> for &SignedBinary {
+    type Output = SignedBinary;
+    fn shr(self, rhs: u64) -> Self::Output {
+        if !self.negative { return SignedBinary::from(&self.magnitude >> rhs) }
+        let mut m = self.magnitude.limbs.clone();
+        dec(&mut m);
+        let mut m = shr_vec(&m, rhs);
+        inc(&mut m);
+        SignedBinary::new(true, BigBinary { limbs: m })
+    }
+}
+
+// x & y, x | y, x ^ y
+impl BitAnd for &BigBinary {
+    type Output = BigBinary;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        let n = self.limbs.len().min(rhs.limbs.len());
+        BigBinary { limbs: zip_vec(&self.limbs[..n], &rhs.limbs[..n], |x, y| x & y) }
+    }
+}
+
+impl BitOr for &BigBinary {
+    type Output = BigBinary;
+    fn bitor(self, rhs: Self) -> Self::Output { BigBinary { limbs: zip_vec(&self.limbs, &rhs.limbs, |x, y| x | y) } }
+}
+
+impl BitXor for &BigBinary {
+    type Output = BigBinary;
+    fn bitxor(self, rhs: Self) -> Self::Output { BigBinary { limbs: zip_vec(&self.limbs, &rhs.limbs, |x, y| x ^ y) } }
+}
+
+impl BitAnd for &SignedBinary {
+    type Output = SignedBinary;
+    fn bitand(self, rhs: Self) -> Self::Output { bitwise(self, rhs, |x, y| x & y) }
+}
+
+impl BitOr for &SignedBinary {
+    type Output = SignedBinary;
+    fn bitor(self, rhs: Self) -> Self::Output { bitwise(self, rhs, |x, y| x | y) }
+}
+
+impl BitXor for &SignedBinary {
+    type Output = SignedBinary;
+    fn bitxor(self, rhs: Self) -> Self::Output { bitwise(self, rhs, |x, y| x ^ y) }
+}
+
+// Остальные варианты (по значению, присваивающие) сводятся к операциям над ссылками
+macro_rules! bit_ops {
+    ($($t:ty),*) => {$(
+        impl Shl<u64> for $t {
+            type Output = $t;
+            fn shl(self, rhs: u64) -> Self::Output { &self << rhs }
+        }
+
+        impl Shr<u64> for $t {
+            type Output = $t;
+            fn shr(self, rhs: u64) -> Self::Output { &self >> rhs }
+        }
+
+        impl ShlAssign<u64> for $t {
+            fn shl_assign(&mut self, rhs: u64) { *self = &*self << rhs; }
+        }
+
+        impl ShrAssign<u64> for $t {
+            fn shr_assign(&mut self, rhs: u64) { *self = &*self >> rhs; }
+        }
+
+        bit_ops!(@op $t, BitAnd, bitand, BitAndAssign, bitand_assign);
+        bit_ops!(@op $t, BitOr, bitor, BitOrAssign, bitor_assign);
+        bit_ops!(@op $t, BitXor, bitxor, BitXorAssign, bitxor_assign);
+    )*};
+    (@op $t:ty, $op:ident, $f:ident, $op_assign:ident, $f_assign:ident) => {
+        impl $op for $t {
+            type Output = $t;
+            fn $f(self, rhs: Self) -> Self::Output { (&self).$f(&rhs) }
+        }
+
+        impl $op<&$t> for $t {
+            type Output = $t;
+            fn $f(self, rhs: &$t) -> Self::Output { (&self).$f(rhs) }
+        }
+
+        impl $op<$t> for &$t {
+            type Output = $t;
+            fn $f(self, rhs: $t) -> Self::Output { self.$f(&rhs) }
+        }
+
+        impl $op_assign for $t {
+            fn $f_assign(&mut self, rhs: Self) { *self = (&*self).$f(&rhs); }
+        }
+
+        impl $op_assign<&$t> for $t {
+            fn $f_assign(&mut self, rhs: &$t) { *self = (&*self).$f(rhs); }
+        }
+    };
+}
+
+bit_ops!(BigBinary, SignedBinary);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn s(x: i128) -> SignedBinary { SignedBinary::from(x) }
+
+    // случайные числа разной длины (до двух слов) и знака
+    fn random_i128(rng: &mut StdRng) -> i128 {
+        rng.r#gen::<i128>() >> rng.gen_range(0..127)
+    }
+
+    #[test]
+    fn unsigned_bits() {
+        let x = BigBinary::from_limbs(&[0, 0b1011, 0]);
+        assert_eq!(x.limbs(), &[0, 0b1011]);
+        assert_eq!(x.bits(), 68);
+        assert_eq!(x.trailing_zeros(), Some(64));
+        assert_eq!(x.count_ones(), 3);
+        assert!(x.bit(64) && x.bit(65) && !x.bit(66) && x.bit(67) && !x.bit(0) && !x.bit(1000));
+        assert_eq!(format!("{x:#x}"), "0xb0000000000000000");
+        assert_eq!(format!("{:b}", BigBinary::from(5)), "101");
+        assert_eq!(BigBinary::zero().bits(), 0);
+        assert_eq!(BigBinary::zero().trailing_zeros(), None);
+        assert_eq!(format!("{:x}", BigBinary::zero()), "0");
+        // set_bit дописывает и убирает старшие слова
+        let mut y = BigBinary::zero();
+        y.set_bit(200, true);
+        assert_eq!(y.bits(), 201);
+        y.set_bit(3, true);
+        y.set_bit(200, false);
+        assert_eq!(y, BigBinary::from(8));
+        y.set_bit(1000, false);
+        assert_eq!(y, BigBinary::from(8));
+    }
+
+    #[test]
+    fn unsigned_shifts() {
+        let x = BigBinary::from(0xdead_beef_u128 << 40);
+        assert_eq!(&x << 0, x);
+        assert_eq!(&(&x << 64) >> 64, x);
+        assert_eq!(&(&x << 100) >> 100, x);
+        assert_eq!((&x << 64).limbs()[0], 0);
+        assert_eq!(&x >> 40, BigBinary::from(0xdead_beef));
+        assert_eq!(&x >> 1000, BigBinary::zero());
+        assert_eq!(&BigBinary::zero() << 100, BigBinary::zero());
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..1000 {
+            let v = rng.r#gen::<u128>() >> rng.gen_range(0..128);
+            let k = rng.gen_range(0..128);
+            assert_eq!(BigBinary::from(v) >> k, BigBinary::from(v >> k), "{v} >> {k}");
+            let k = v.leading_zeros().min(rng.gen_range(0..128));
+            assert_eq!(BigBinary::from(v) << k as u64, BigBinary::from(v << k), "{v} << {k}");
+        }
+    }
+
+    #[test]
+    fn unsigned_bitwise() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..1000 {
+            let a = rng.r#gen::<u128>() >> rng.gen_range(0..128);
+            let b = rng.r#gen::<u128>() >> rng.gen_range(0..128);
+            let (x, y) = (BigBinary::from(a), BigBinary::from(b));
+            assert_eq!(&x & &y, BigBinary::from(a & b));
+            assert_eq!(&x | &y, BigBinary::from(a | b));
+            assert_eq!(&x ^ &y, BigBinary::from(a ^ b));
+            assert_eq!(x.count_ones(), a.count_ones() as u64);
+            assert_eq!(x.cmp(&y), a.cmp(&b));
+        }
+    }
+
+    #[test]
+    fn signed_shifts() {
+        // сдвиг вправо округляет вниз, как у i128
+        assert_eq!(&s(-1) >> 10, s(-1));
+        assert_eq!(&s(-5) >> 1, s(-3));
+        assert_eq!(&s(-4) >> 1, s(-2));
+        assert_eq!(&s(-(1 << 100)) >> 1000, s(-1));
+        assert_eq!(&s(5) >> 1000, s(0));
+        assert_eq!(&s(-3) << 70, s(-3 << 70));
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..1000 {
+            let a = random_i128(&mut rng);
+            let k = rng.gen_range(0..128);
+            assert_eq!(s(a) >> k, s(a >> k), "{a} >> {k}");
+            let k = rng.gen_range(0..a.unsigned_abs().leading_zeros().max(1));
+            assert_eq!(s(a) << k as u64, s(a << k), "{a} << {k}");
+        }
+    }
+
+    #[test]
+    fn signed_bitwise() {
+        // дополнительный код, как у i128
+        assert_eq!(&s(-6) & &s(5), s(0));
+        assert_eq!(&s(-6) | &s(5), s(-1));
+        assert_eq!(&s(-6) ^ &s(-5), s(1));
+        assert_eq!(!s(0), s(-1));
+        assert_eq!(!s(-1), s(0));
+        assert_eq!(-s(0), s(0));
+        assert!(!(-s(0)).is_negative());
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..1000 {
+            let (a, b) = (random_i128(&mut rng), random_i128(&mut rng));
+            let (x, y) = (s(a), s(b));
+            assert_eq!(&x & &y, s(a & b), "{a} & {b}");
+            assert_eq!(&x | &y, s(a | b), "{a} | {b}");
+            assert_eq!(&x ^ &y, s(a ^ b), "{a} ^ {b}");
+            assert_eq!(!&x, s(!a), "!{a}");
+            assert_eq!(x.cmp(&y), a.cmp(&b));
+            let mut z = x.clone();
+            z ^= &y;
+            z &= y.clone();
+            z |= &x;
+            assert_eq!(z, s(((a ^ b) & b) | a));
+        }
+    }
+
+    #[test]
+    fn signed_bit_and_set_bit() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..300 {
+            let a = random_i128(&mut rng);
+            let x = s(a);
+            for i in 0..128 {
+                assert_eq!(x.bit(i), (a >> i) & 1 == 1, "bit {i} of {a}");
+            }
+            // дальше - знаковое расширение
+            assert_eq!(x.bit(1000), a < 0);
+            let i = rng.gen_range(0..127);
+            let mut y = x.clone();
+            y.set_bit(i, true);
+            assert_eq!(y, s(a | (1 << i)), "{a}: set {i}");
+            y.set_bit(i, false);
+            assert_eq!(y, s(a & !(1 << i)), "{a}: clear {i}");
+        }
+        // у отрицательного числа выставленный старший бит ничего не меняет
+        let mut y = s(-7);
+        y.set_bit(500, true);
+        assert_eq!(y, s(-7));
+        y.set_bit(500, false);
+        // -7 - 2^500
+        assert_eq!(y, SignedBinary::new(true, &BigBinary::from(7) | &(BigBinary::one() << 500)));
+    }
+
+    #[test]
+    fn signed_counts() {
+        assert_eq!(s(0b1011).count_ones(), Some(3));
+        assert_eq!(s(0).count_ones(), Some(0));
+        assert_eq!(s(-1).count_ones(), None);
+        assert_eq!(s(-0b1011).magnitude().count_ones(), 3);
+        assert_eq!(s(-40).trailing_zeros(), Some(3));
+        assert_eq!(s(-40).bits(), 6);
+        assert_eq!(s(-40).to_string(), "-40");
+        assert_eq!(format!("{:>5}", s(-40)), "  -40");
+    }
+}
@@ -0,0 +1,253 @@
+use crate::big::BigDecimal;
+use crate::oper::{add_assign_vec, divmod_vec, from_u128, mul_vec, sqr};
+use crate::sieve::primes_upto;
+
+// Число листьев, начиная с которого ветви дерева считаются параллельно
+const PAR_LIMIT: usize = 64;
+
+// Наибольшее n, для которого биномиальный коэффициент считается через разложение на простые
+const PRIME_LIMIT: u64 = 1 << 26;
+
+// C(n, k) считается как n(n - 1)...(n - k + 1) / k!, если k <= SMALL_K или k < n / SMALL_K_RATIO
+// (по замерам: при таких k произведение быстрее решета до n)
+const SMALL_K: u64 = 64;
+const SMALL_K_RATIO: u64 = 4096;
+
+//-------------------------------------------------------------------------------------------------
+// Дерево произведений: множители перемножаются попарно,
+// так что большие умножения выполняются над числами близкого размера
+pub fn product_tree(leaves: &[Vec<u8>]) -> Vec<u8> {
+    match leaves.len() {
+        0 => vec![1u8],
+        1 => leaves[0].clone(),
+        n => {
+            let (l, r) = leaves.split_at(n / 2);
+            let (x, y) = if n >= PAR_LIMIT {
+                rayon::join(|| product_tree(l), || product_tree(r))
+            } else {
+                (product_tree(l), product_tree(r))
+            };
+            mul_vec(&x, &y)
+        }
+    }
+}
+
+// Сумма попарно, по той же схеме
+pub fn sum_tree(leaves: &[Vec<u8>]) -> Vec<u8> {
+    match leaves.len() {
+        0 => vec![0u8],
+        1 => leaves[0].clone(),
+        n => {
+            let (l, r) = leaves.split_at(n / 2);
+            let (mut x, y) = if n >= PAR_LIMIT {
+                rayon::join(|| sum_tree(l), || sum_tree(r))
+            } else {
+                (sum_tree(l), sum_tree(r))
+            };
+            add_assign_vec(&mut x, &y);
+            x
+        }
+    }
+}
+
+// Произведение чисел последовательности, пустое произведение равно 1
+pub fn product<I: IntoIterator<Item = BigDecimal>>(factors: I) -> BigDecimal {
+    let leaves: Vec<Vec<u8>> = factors.into_iter().map(|x| x.digits).collect();
+    BigDecimal { digits: product_tree(&leaves) }
+}
+
+// Сумма чисел последовательности, пустая сумма равна 0
+pub fn sum<I: IntoIterator<Item = BigDecimal>>(terms: I) -> BigDecimal {
+    let leaves: Vec<Vec<u8>> = terms.into_iter().map(|x| x.digits).collect();
+    BigDecimal { digits: sum_tree(&leaves) }
+}
+
+// Произведение целых чисел u64 через дерево произведений
+pub fn product_u64<I: IntoIterator<Item = u64>>(factors: I) -> BigDecimal {
+    BigDecimal { digits: product_tree(&pack(factors)) }
+}
+
+// Подряд идущие множители собираем в u128, пока произведение помещается
+fn pack<I: IntoIterator<Item = u64>>(factors: I) -> Vec<Vec<u8>> {
+    let mut res = Vec::new();
+    let mut acc = 1u128;
+    for x in factors {
+        match acc.checked_mul(x as u128) {
+            Some(y) => acc = y,
+            None => {
+                res.push(from_u128(acc));
+                acc = x as u128;
+            }
+        }
+    }
+    res.push(from_u128(acc));
+    res
+}
+
+// Показатель простого p в n! (формула Лежандра)
+fn legendre(mut n: u64, p: u64) -> u64 {
+    let mut e = 0;
+    while n >= p {
+        n /= p;
+        e += n;
+    }
+    e
+}
+
+// Произведение p^e по всем простым p
+fn from_exponents<I: IntoIterator<Item = (u64, u64)>>(powers: I) -> Vec<u8> {
+    product_tree(&pack(powers.into_iter().flat_map(|(p, e)| std::iter::repeat_n(p, e as usize))))
+}
+
+//-------------------------------------------------------------------------------------------------
+// Факториалы
+
+// n! через "качающийся" факториал: n! = ((n/2)!)^2 * swing(n)
+pub fn factorial(n: u64) -> BigDecimal {
+    let primes = primes_upto(n);
+    BigDecimal { digits: factorial_swing(n, &primes) }
+}
+
+fn factorial_swing(n: u64, primes: &[u64]) -> Vec<u8> {
+    if n < 2 { return vec![1u8] }
+    let (h, s) = rayon::join(|| factorial_swing(n / 2, primes), || swing(n, primes));
+    mul_vec(&sqr(&h), &s)
+}
+
+// swing(n) = n! / ((n/2)!)^2, показатель p равен числу нечетных n / p^i
+fn swing(n: u64, primes: &[u64]) -> Vec<u8> {
+    from_exponents(primes.iter().take_while(|&&p| p <= n).map(|&p| {
+        let mut q = n;
+        let mut e = 0;
+        while q >= p {
+            q /= p;
+            e += q & 1;
+        }
+        (p, e)
+    }))
+}
+
+// n!! = n * (n - 2) * (n - 4) * ...
+pub fn double_factorial(n: u64) -> BigDecimal {
+    if n.is_multiple_of(2) {
+        // (2k)!! = 2^k * k!
+        let k = n / 2;
+        let f = factorial(k);
+        let p = product_tree(&pack(std::iter::repeat_n(2, k as usize)));
+        BigDecimal { digits: mul_vec(&p, &f.digits) }
+    } else {
+        product_u64((1..=n).step_by(2))
+    }
+}
+
+// Произведение простых чисел, не больших n
+pub fn primorial(n: u64) -> BigDecimal {
+    product_u64(primes_upto(n))
+}
+
+//-------------------------------------------------------------------------------------------------
+// Биномиальные коэффициенты
+
+// C(n, k)
+pub fn binomial(n: u64, k: u64) -> BigDecimal {
+    if k > n { return BigDecimal::zero() }
+    let k = k.min(n - k);
+    // разложение на простые требует решета до n, при малом k быстрее произведение
+    if n <= PRIME_LIMIT && k > SMALL_K && k.saturating_mul(SMALL_K_RATIO) >= n {
+        // показатель p равен v_p(n!) - v_p(k!) - v_p((n - k)!)
+        let primes = primes_upto(n);
+        let digits = from_exponents(primes.into_iter()
+            .map(|p| (p, legendre(n, p) - legendre(k, p) - legendre(n - k, p))));
+        return BigDecimal { digits }
+    }
+    // n(n - 1)...(n - k + 1) / k!
+    let num = product_tree(&pack(n - k + 1..=n));
+    BigDecimal { digits: divmod_vec(&num, &factorial(k).digits).0 }
+}
+
+// (k1 + k2 + ... + km)! / (k1! k2! ... km!)
+// = C(k1 + k2, k2) * C(k1 + k2 + k3, k3) * ...
+pub fn multinomial(ks: &[u64]) -> BigDecimal {
+    let mut n = 0u64;
+    let mut leaves = Vec::with_capacity(ks.len());
+    for &k in ks {
+        n = n.checked_add(k).expect("multinomial overflow");
+        leaves.push(binomial(n, k).digits);
+    }
+    BigDecimal { digits: product_tree(&leaves) }
+}
+
+// n-е число Каталана: C(2n, n) / (n + 1)
+pub fn catalan(n: u64) -> BigDecimal {
+    let c = binomial(n.checked_mul(2).expect("catalan overflow"), n);
+    BigDecimal { digits: divmod_vec(&c.digits, &from_u128(n as u128 + 1)).0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    // последние 30 цифр и длина числа
+    fn tail(x: &BigDecimal) -> (String, usize) {
+        let s = x.to_string();
+        (s[s.len().saturating_sub(30)..].trim_start_matches('0').to_string(), s.len())
+    }
+
+    #[test]
+    fn binomial_small_k() {
+        assert_eq!(binomial(0, 0), BigDecimal::one());
+        assert_eq!(binomial(5, 7), BigDecimal::zero());
+        assert_eq!(binomial(10, 3), big("120"));
+        assert_eq!(binomial(100, 30), big("29372339821610944823963760"));
+        // k <= SMALL_K
+        assert_eq!(tail(&binomial(5000, 64)), ("510227472907479005669904486250".to_string(), 148));
+        // k * SMALL_K_RATIO < n
+        assert_eq!(tail(&binomial(1_000_000, 100)), ("619115198117741097602556240000".to_string(), 443));
+        // n > PRIME_LIMIT
+        assert_eq!(tail(&binomial(1 << 27, 70)), ("107922646806211847713361756160".to_string(), 469));
+    }
+
+    #[test]
+    fn binomial_prime_swing() {
+        assert_eq!(binomial(200, 100), big("90548514656103281165404177077484163874504589675413336841320"));
+        assert_eq!(tail(&binomial(5000, 65)), ("668966250327944184409977602000".to_string(), 150));
+        assert_eq!(tail(&binomial(2000, 1000)), ("293155911108976733963991149120".to_string(), 601));
+        // граница k * SMALL_K_RATIO = n
+        assert_eq!(tail(&binomial(266240, 65)), ("956503254206821473132815323136".to_string(), 262));
+    }
+
+    #[test]
+    fn binomial_paths_agree() {
+        // тождество Паскаля на границах выбора метода
+        for (n, k) in [(200u64, 65u64), (5000, 65), (266241, 65), (266240, 66), (300, 150)] {
+            assert_eq!(binomial(n, k), &binomial(n - 1, k - 1) + &binomial(n - 1, k), "C({n}, {k})");
+            assert_eq!(binomial(n, k), binomial(n, n - k), "C({n}, {k})");
+        }
+    }
+
+    #[test]
+    fn factorials_known() {
+        assert_eq!(factorial(0), BigDecimal::one());
+        assert_eq!(factorial(1), BigDecimal::one());
+        assert_eq!(factorial(30), big("265252859812191058636308480000000"));
+        assert_eq!(factorial(500), product_u64(1..=500));
+        assert_eq!(double_factorial(31), big("191898783962510625"));
+        assert_eq!(double_factorial(30), big("42849873690624000"));
+        assert_eq!(double_factorial(0), BigDecimal::one());
+        assert_eq!(primorial(30), big("6469693230"));
+        assert_eq!(primorial(1), BigDecimal::one());
+    }
+
+    #[test]
+    fn catalan_multinomial_known() {
+        let first: Vec<String> = (0..8).map(|n| catalan(n).to_string()).collect();
+        assert_eq!(first, ["1", "1", "2", "5", "14", "42", "132", "429"]);
+        assert_eq!(catalan(20), big("6564120420"));
+        assert_eq!(multinomial(&[2, 3, 5]), big("2520"));
+        assert_eq!(multinomial(&[]), BigDecimal::one());
+        assert_eq!(sum((1..=100u64).map(|x| big(&x.to_string()))), big("5050"));
+        assert_eq!(product(Vec::new()), BigDecimal::one());
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use lazy_static::lazy_static;
+use crate::float::BigFloat;
+use crate::func::{arc_inv_series, int, sum_series, Exact, GUARD};
+
+//-------------------------------------------------------------------------------------------------
+// Математические константы с заданным числом значащих цифр
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Constant {
+    Pi,
+    E,
+    Sqrt2,
+    Ln2,
+    Ln10,
+    Phi
+}
+
+lazy_static!(
+    // самое точное из уже вычисленных значений каждой константы
+    static ref CONST_CASH: Mutex<HashMap<Constant, BigFloat>> = Mutex::new(HashMap::new());
+);
+
+// Значение константы с точностью digits
+// Повторный запрос с той же или меньшей точностью берется из кэша
+pub fn constant(c: Constant, digits: usize) -> BigFloat {
+    if let Some(x) = CONST_CASH.lock().unwrap().get(&c) {
+        if x.precision() >= digits + GUARD {
+            return x.clone().with_precision(digits)
+        }
+    }
+    // считаем с запасом, чтобы округление из кэша было верным
+    let wp = digits + GUARD;
+    let x = match c {
+        Constant::Pi => pi_series(wp),
+        Constant::E => BigFloat::one(wp).exp(),
+        Constant::Sqrt2 => int(2, wp).sqrt(),
+        Constant::Ln2 => ln2_series(wp),
+        Constant::Ln10 => ln10_series(wp),
+        Constant::Phi => (BigFloat::one(wp) + int(5, wp).sqrt()) / int(2, wp)
+    };
+    let mut cash = CONST_CASH.lock().unwrap();
+    match cash.get(&c) {
+        Some(y) if y.precision() >= wp => {},
+        _ => { cash.insert(c, x.clone()); }
+    }
+    x.with_precision(digits)
+}
+
+// pi
+pub fn pi(digits: usize) -> BigFloat { constant(Constant::Pi, digits) }
+
+// e
+pub fn e(digits: usize) -> BigFloat { constant(Constant::E, digits) }
+
+// sqrt(2)
+pub fn sqrt2(digits: usize) -> BigFloat { constant(Constant::Sqrt2, digits) }
+
+// ln(2)
+pub fn ln2(digits: usize) -> BigFloat { constant(Constant::Ln2, digits) }
+
+// ln(10)
+pub fn ln10(digits: usize) -> BigFloat { constant(Constant::Ln10, digits) }
+
+// золотое сечение (1 + sqrt(5)) / 2
+pub fn phi(digits: usize) -> BigFloat { constant(Constant::Phi, digits) }
+
+//-------------------------------------------------------------------------------------------------
+// Формула Чудновских (около 14 верных цифр на член ряда)
+// pi = 426880 * sqrt(10005) / S, S = sum a(n) * prod p(i) / q(i),
+// a(n) = 13591409 + 545140134 n, p(n) = -(6n - 5)(2n - 1)(6n - 1), q(n) = n^3 * 640320^3 / 24
+fn pi_series(precision: usize) -> BigFloat {
+    let wp = precision + GUARD;
+    let n = (wp as f64 / 14.18) as u64 + 2;
+    let (s, root) = rayon::join(
+        || sum_series(n, wp, &|i| {
+            let a = Exact::int(13591409 + 545140134 * i as i128);
+            if i == 0 { return [a, Exact::one(), Exact::one(), Exact::one()] }
+            let i = i as i128;
+            let p = Exact::int(-(6 * i - 5) * (2 * i - 1) * (6 * i - 1));
+            let q = Exact::int(i * i * i).mul(&Exact::int(10939058860032000));
+            [a, Exact::one(), p, q]
+        }),
+        || int(10005, wp).sqrt()
+    );
+    (int(426880, wp) * root / s).with_precision(precision)
+}
+
+// atanh(1/251), atanh(1/449), atanh(1/4801), atanh(1/8749)
+fn atanh_basis(wp: usize) -> [BigFloat; 4] {
+    let ((a, b), (c, d)) = rayon::join(
+        || rayon::join(|| arc_inv_series(251, false, wp), || arc_inv_series(449, false, wp)),
+        || rayon::join(|| arc_inv_series(4801, false, wp), || arc_inv_series(8749, false, wp))
+    );
+    [a, b, c, d]
+}
+
+// ln(2) = 144 atanh(1/251) + 54 atanh(1/449) - 38 atanh(1/4801) + 62 atanh(1/8749)
+fn ln2_series(precision: usize) -> BigFloat {
+    let wp = precision + GUARD;
+    let [a, b, c, d] = atanh_basis(wp);
+    (a * int(144, wp) + b * int(54, wp) - c * int(38, wp) + d * int(62, wp)).with_precision(precision)
+}
+
+// ln(10) = 478 atanh(1/251) + 180 atanh(1/449) - 126 atanh(1/4801) + 206 atanh(1/8749)
+fn ln10_series(precision: usize) -> BigFloat {
+    let wp = precision + GUARD;
+    let [a, b, c, d] = atanh_basis(wp);
+    (a * int(478, wp) + b * int(180, wp) - c * int(126, wp) + d * int(206, wp)).with_precision(precision)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 130 значащих цифр
+    const PI: &str = "3.141592653589793238462643383279502884197169399375105820974944592307816406286208998628034825342117067982148086513282306647093844610";
+    const E: &str = "2.718281828459045235360287471352662497757247093699959574966967627724076630353547594571382178525166427427466391932003059921817413597";
+    const SQRT2: &str = "1.414213562373095048801688724209698078569671875376948073176679737990732478462107038850387534327641572735013846230912297024924836056";
+    const LN2: &str = "0.6931471805599453094172321214581765680755001343602552541206800094933936219696947156058633269964186875420014810205706857336855202358";
+    const LN10: &str = "2.302585092994045684017991454684364207601101488628772976033327900967572609677352480235997205089598298341967784042286248633409525465";
+    const PHI: &str = "1.618033988749894848204586834365638117720309179805762862135448622705260462818902449707207204189391137484754088075386891752126633862";
+
+    #[test]
+    fn known_digits() {
+        let cases = [
+            (Constant::Pi, PI), (Constant::E, E), (Constant::Sqrt2, SQRT2),
+            (Constant::Ln2, LN2), (Constant::Ln10, LN10), (Constant::Phi, PHI)
+        ];
+        for (c, expected) in cases {
+            // с правильным округлением до digits цифр
+            for digits in [1, 5, 20, 100, 120] {
+                assert_eq!(constant(c, digits), BigFloat::parse(expected, digits), "{digits} digits of {c:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn cached_value_rounded() {
+        // после вычисления с большой точностью меньшая берется из кэша
+        let x = ln10(110);
+        assert_eq!(x.precision(), 110);
+        assert_eq!(ln10(17), BigFloat::parse(LN10, 17));
+        assert_eq!(ln10(17).precision(), 17);
+        assert_eq!(constant(Constant::Ln10, 110), x);
+        assert_eq!(pi(3).to_string(), "3.14");
+        assert_eq!(pi(4).to_string(), "3.142");
+        assert_eq!(e(3).to_string(), "2.72");
+        assert_eq!((sqrt2(5), ln2(5), phi(5)), (BigFloat::parse(SQRT2, 5), BigFloat::parse(LN2, 5), BigFloat::parse(PHI, 5)));
+    }
+}
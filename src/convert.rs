@@ -0,0 +1,215 @@
+use std::fmt::{Display, Formatter};
+use crate::big::BigDecimal;
+use crate::oper::{divmod_vec, from_u128, mul_small, mul_vec, sqr};
+
+//-------------------------------------------------------------------------------------------------
+// Преобразования между BigDecimal и примитивными числовыми типами
+
+// Ошибка: число не помещается в целевой тип
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryFromBigError;
+
+impl Display for TryFromBigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "number out of range for target type")
+    }
+}
+
+impl std::error::Error for TryFromBigError {}
+
+// Ошибка: число с плавающей точкой не является конечным неотрицательным целым
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TryFromFloatError;
+
+impl Display for TryFromFloatError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "float is not a finite non-negative integer")
+    }
+}
+
+impl std::error::Error for TryFromFloatError {}
+
+// Беззнаковые целые преобразуются без потерь
+macro_rules! from_unsigned {
+    ($($t:ty),*) => {$(
+        impl From<$t> for BigDecimal {
+            fn from(value: $t) -> Self {
+                Self { digits: from_u128(value as u128) }
+            }
+        }
+    )*}
+}
+
+from_unsigned!(u8, u16, u32, u64, u128, usize);
+
+// Обратно - только если число не больше максимума типа
+macro_rules! try_into_primitive {
+    ($($t:ty),*) => {$(
+        impl TryFrom<&BigDecimal> for $t {
+            type Error = TryFromBigError;
+            fn try_from(value: &BigDecimal) -> Result<Self, Self::Error> {
+                value.to_u128().and_then(|x| <$t>::try_from(x).ok()).ok_or(TryFromBigError)
+            }
+        }
+
+        impl TryFrom<BigDecimal> for $t {
+            type Error = TryFromBigError;
+            fn try_from(value: BigDecimal) -> Result<Self, Self::Error> {
+                <$t>::try_from(&value)
+            }
+        }
+    )*}
+}
+
+try_into_primitive!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl BigDecimal {
+    // Значение, если оно помещается в u128
+    fn to_u128(&self) -> Option<u128> {
+        if self.digits.len() > 39 { return None }
+        self.digits.iter().rev().try_fold(0u128, |acc, &x| acc.checked_mul(10)?.checked_add(x as u128))
+    }
+
+    // Ближайшее число f64 (при равенстве - с четной мантиссой), бесконечность при переполнении
+    pub fn to_f64(&self) -> f64 {
+        // больше 10^309 > f64::MAX
+        if self.digits.len() > 310 { return f64::INFINITY }
+        let (m, shift) = self.top_bits();
+        m as f64 * 2f64.powi(shift as i32)
+    }
+
+    // Ближайшее число f32, бесконечность при переполнении
+    pub fn to_f32(&self) -> f32 {
+        // больше 10^40 > f32::MAX
+        if self.digits.len() > 40 { return f32::INFINITY }
+        let (m, shift) = self.top_bits();
+        m as f32 * 2f32.powi(shift as i32)
+    }
+
+    // Число в виде m * 2^shift с точностью не хуже 63 старших битов:
+    // младший бит m "липкий" - установлен, если отброшенные биты не все нулевые,
+    // поэтому однократное округление m до мантиссы дает правильный результат
+    fn top_bits(&self) -> (u128, u64) {
+        if let Some(x) = self.to_u128() { return (x, 0) }
+        // x >= 10^(len - 1) >= 2^shift * 2^64, x < 10^len < 2^shift * 2^69
+        let shift = ((self.digits.len() - 1) as f64 * std::f64::consts::LOG2_10) as u64 - 64;
+        let (q, r) = divmod_vec(&self.digits, &pow2(shift));
+        let m = BigDecimal { digits: q }.to_u128().unwrap();
+        (if r == [0u8] { m } else { m | 1 }, shift)
+    }
+}
+
+// 2^k
+fn pow2(k: u64) -> Vec<u8> {
+    let mut res = vec![1u8];
+    for i in (0..64 - k.leading_zeros()).rev() {
+        res = sqr(&res);
+        if (k >> i) & 1 == 1 { res = mul_small(&res, 2); }
+    }
+    res
+}
+
+// Целое конечное неотрицательное число с плавающей точкой преобразуется точно
+impl TryFrom<f64> for BigDecimal {
+    type Error = TryFromFloatError;
+    fn try_from(value: f64) -> Result<Self, Self::Error> {
+        if !value.is_finite() || value < 0. || value.fract() != 0. { return Err(TryFromFloatError) }
+        if value < 2f64.powi(128) { return Ok(Self { digits: from_u128(value as u128) }) }
+        // value = m * 2^e, m - 53-битная мантисса
+        let bits = value.to_bits();
+        let e = ((bits >> 52) & 0x7ff) - 1075;
+        let m = (bits & ((1 << 52) - 1)) | (1 << 52);
+        Ok(Self { digits: mul_vec(&from_u128(m as u128), &pow2(e)) })
+    }
+}
+
+impl TryFrom<f32> for BigDecimal {
+    type Error = TryFromFloatError;
+    fn try_from(value: f32) -> Result<Self, Self::Error> {
+        Self::try_from(value as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    // m * 2^e + a
+    fn scaled(m: u128, e: u64, a: u128) -> BigDecimal {
+        BigDecimal { digits: mul_vec(&from_u128(m), &pow2(e)) } + BigDecimal::from(a)
+    }
+
+    #[test]
+    fn to_f64_rounding() {
+        assert_eq!(BigDecimal::zero().to_f64(), 0.);
+        assert_eq!(big("12345678901234567890123456789").to_f64(), 1.2345678901234568e28);
+        // чуть больше 2^53: середина округляется к четной мантиссе
+        let p = 1u128 << 53;
+        assert_eq!(BigDecimal::from(p + 1).to_f64(), p as f64);
+        assert_eq!(BigDecimal::from(p + 2).to_f64(), (p + 2) as f64);
+        assert_eq!(BigDecimal::from(p + 3).to_f64(), (p + 4) as f64);
+        // то же за пределами u128: середина и чуть больше середины ("липкий" бит)
+        assert_eq!(scaled(p + 1, 100, 0).to_f64(), 2f64.powi(153));
+        assert_eq!(scaled(p + 1, 100, 1).to_f64(), (p + 2) as f64 * 2f64.powi(100));
+        assert_eq!(scaled(p + 3, 100, 0).to_f64(), (p + 4) as f64 * 2f64.powi(100));
+        // f64::MAX = (2^53 - 1) 2^971, середина до 2^1024 уже переполняется
+        assert_eq!(scaled(p - 1, 971, 0).to_f64(), f64::MAX);
+        let half = BigDecimal { digits: pow2(970) };
+        assert_eq!((scaled(p - 1, 971, 0) + &half - BigDecimal::one()).to_f64(), f64::MAX);
+        assert_eq!((scaled(p - 1, 971, 0) + &half).to_f64(), f64::INFINITY);
+        assert_eq!(big(&format!("1{}", "0".repeat(400))).to_f64(), f64::INFINITY);
+        assert_eq!(big(&format!("1{}", "0".repeat(300))).to_f64(), 1e300);
+    }
+
+    #[test]
+    fn to_f32_rounding() {
+        let p = 1u128 << 24;
+        assert_eq!(BigDecimal::from(p + 1).to_f32(), p as f32);
+        assert_eq!(BigDecimal::from(p + 3).to_f32(), (p + 4) as f32);
+        // f32::MAX = (2^24 - 1) 2^104 - 39 цифр, помещается в u128
+        assert_eq!(big("340282346638528859811704183484516925440").to_f32(), f32::MAX);
+        assert_eq!(big("340282356779733661637539395458142568447").to_f32(), f32::MAX);
+        assert_eq!(big("340282356779733661637539395458142568448").to_f32(), f32::INFINITY);
+        // 40 цифр: меньше u128::MAX, но больше f32::MAX
+        assert_eq!(big("1000000000000000000000000000000000000000").to_f32(), f32::INFINITY);
+        assert_eq!(BigDecimal::from(u128::MAX).to_f32(), f32::INFINITY);
+        assert_eq!(big(&format!("1{}", "0".repeat(40))).to_f32(), f32::INFINITY);
+        assert_eq!(big("100000000000000000000000000000000000000").to_f32(), 1e38);
+    }
+
+    #[test]
+    fn try_from_float() {
+        for x in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY, -1., -1e300, 0.5, 1e15 + 0.5] {
+            assert_eq!(BigDecimal::try_from(x), Err(TryFromFloatError), "{x}");
+        }
+        assert_eq!(BigDecimal::try_from(f32::NAN), Err(TryFromFloatError));
+        assert_eq!(BigDecimal::try_from(-0.), Ok(BigDecimal::zero()));
+        assert_eq!(BigDecimal::try_from(9007199254740993f64), Ok(big("9007199254740992")));
+        assert_eq!(BigDecimal::try_from(16777217f32), Ok(big("16777216")));
+        // от 2^128 - через мантиссу и степень двойки
+        assert_eq!(BigDecimal::try_from(2f64.powi(128)), Ok(big("340282366920938463463374607431768211456")));
+        assert_eq!(BigDecimal::try_from(1.5 * 2f64.powi(200)),
+            Ok(big("2410407066388485413312943138511743903783304490674189252952064")));
+        assert_eq!(BigDecimal::try_from(f64::MAX), Ok(scaled((1 << 53) - 1, 971, 0)));
+        assert_eq!(BigDecimal::try_from(f32::MAX), Ok(big("340282346638528859811704183484516925440")));
+        // туда и обратно
+        for x in [1e20, 1e300, 3.0 * 2f64.powi(500), f64::MAX] {
+            assert_eq!(BigDecimal::try_from(x).unwrap().to_f64(), x);
+        }
+    }
+
+    #[test]
+    fn primitive_integers() {
+        assert_eq!(BigDecimal::from(u64::MAX), big("18446744073709551615"));
+        assert_eq!(BigDecimal::from(0u8), BigDecimal::zero());
+        assert_eq!(u8::try_from(&big("255")), Ok(255));
+        assert_eq!(u8::try_from(&big("256")), Err(TryFromBigError));
+        assert_eq!(i64::try_from(big("9223372036854775807")), Ok(i64::MAX));
+        assert_eq!(i64::try_from(big("9223372036854775808")), Err(TryFromBigError));
+        assert_eq!(u128::try_from(BigDecimal::from(u128::MAX)), Ok(u128::MAX));
+        assert_eq!(u128::try_from(BigDecimal::from(u128::MAX) + BigDecimal::one()), Err(TryFromBigError));
+        assert_eq!(u128::try_from(big(&format!("1{}", "0".repeat(50)))), Err(TryFromBigError));
+    }
+}
@@ -0,0 +1,318 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+use crate::big::BigDecimal;
+use crate::float::{round_digits, RoundingMode};
+use crate::oper::{add_small_vec, add_vec, cmp_vec, divmod_vec, mul_small, mul_vec, shl10, sub_vec, trim_vec};
+
+//-------------------------------------------------------------------------------------------------
+// Десятичные числа с фиксированным числом знаков после запятой
+// значение = (-1)^negative * unscaled / 10^scale
+#[derive(Clone)]
+pub struct Decimal {
+    negative: bool,
+    unscaled: BigDecimal,
+    scale: u32
+}
+
+// Ошибка разбора строки
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseDecimalError;
+
+impl Display for ParseDecimalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid decimal literal")
+    }
+}
+
+impl std::error::Error for ParseDecimalError {}
+
+impl Decimal {
+    pub fn zero() -> Self {
+        Self { negative: false, unscaled: BigDecimal::zero(), scale: 0 }
+    }
+    // (-1)^negative * unscaled / 10^scale
+    pub fn new(negative: bool, unscaled: BigDecimal, scale: u32) -> Self {
+        let negative = negative && !unscaled.is_zero();
+        Self { negative, unscaled, scale }
+    }
+    pub fn unscaled(&self) -> &BigDecimal { &self.unscaled }
+    pub fn scale(&self) -> u32 { self.scale }
+    pub fn is_zero(&self) -> bool { self.unscaled.is_zero() }
+    pub fn is_negative(&self) -> bool { self.negative }
+    // |x|
+    pub fn abs(&self) -> Self {
+        Self { negative: false, ..self.clone() }
+    }
+    // изменить число знаков после запятой с округлением
+    pub fn rescale(&self, scale: u32, mode: RoundingMode) -> Self {
+        let digits = &self.unscaled.digits;
+        let m = if scale >= self.scale {
+            shl10(digits, (scale - self.scale) as usize)
+        } else {
+            round_digits(digits, (self.scale - scale) as usize, self.negative, mode)
+        };
+        Self::new(self.negative, BigDecimal { digits: m }, scale)
+    }
+    // частное с scale знаками после запятой и округлением mode
+    pub fn div(&self, rhs: &Self, scale: u32, mode: RoundingMode) -> Self {
+        if rhs.is_zero() { panic!("division by zero"); }
+        // a / 10^sa : b / 10^sb = (a * 10^(sb + scale - sa) / b) / 10^scale
+        let shift = rhs.scale as i64 + scale as i64 - self.scale as i64;
+        let (a, b) = if shift >= 0 {
+            (shl10(&self.unscaled.digits, shift as usize), rhs.unscaled.digits.clone())
+        } else {
+            (self.unscaled.digits.clone(), shl10(&rhs.unscaled.digits, (-shift) as usize))
+        };
+        let negative = self.negative != rhs.negative;
+        let (mut q, r) = divmod_vec(&a, &b);
+        // первая отброшенная цифра и остаток после нее
+        let (first, rest) = divmod_vec(&mul_small(&r, 10), &b);
+        if mode.round_up(negative, q[0] & 1 == 1, first[0], rest != [0u8]) {
+            add_small_vec(&mut q, 1);
+        }
+        Self::new(negative, BigDecimal { digits: q }, scale)
+    }
+
+    // Мантиссы обоих чисел при общем числе знаков после запятой
+    fn aligned(&self, rhs: &Self) -> (Vec<u8>, Vec<u8>, u32) {
+        let scale = self.scale.max(rhs.scale);
+        (
+            shl10(&self.unscaled.digits, (scale - self.scale) as usize),
+            shl10(&rhs.unscaled.digits, (scale - rhs.scale) as usize),
+            scale
+        )
+    }
+}
+
+// x + y (с учетом знаков)
+fn add_decimal(x: &Decimal, y: &Decimal, y_negative: bool) -> Decimal {
+    let (a, b, scale) = x.aligned(y);
+    if x.negative == y_negative {
+        return Decimal::new(x.negative, BigDecimal { digits: add_vec(&a, &b) }, scale)
+    }
+    match cmp_vec(&a, &b) {
+        Ordering::Less => Decimal::new(y_negative, BigDecimal { digits: sub_vec(&b, &a) }, scale),
+        _ => Decimal::new(x.negative, BigDecimal { digits: sub_vec(&a, &b) }, scale)
+    }
+}
+
+// x * y
+fn mul_decimal(x: &Decimal, y: &Decimal) -> Decimal {
+    Decimal::new(
+        x.negative != y.negative,
+        BigDecimal { digits: mul_vec(&x.unscaled.digits, &y.unscaled.digits) },
+        x.scale + y.scale
+    )
+}
+
+impl From<BigDecimal> for Decimal {
+    fn from(value: BigDecimal) -> Self {
+        Self::new(false, value, 0)
+    }
+}
+
+// Разбор строки "[+-]<цифры>[.<цифры>]", число знаков после запятой сохраняется
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s))
+        };
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        if int.is_empty() && frac.is_empty() { return Err(ParseDecimalError) }
+        if !int.bytes().chain(frac.bytes()).all(|x| x.is_ascii_digit()) {
+            return Err(ParseDecimalError)
+        }
+        let mut digits: Vec<u8> = int.bytes().chain(frac.bytes()).rev().map(|x| x - b'0').collect();
+        trim_vec(&mut digits);
+        let scale = u32::try_from(frac.len()).map_err(|_| ParseDecimalError)?;
+        Ok(Self::new(negative, BigDecimal { digits }, scale))
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let scale = self.scale as usize;
+        let digits = &self.unscaled.digits;
+        let mut s = String::with_capacity(digits.len().max(scale + 1) + 2);
+        if self.negative { s.push('-'); }
+        if digits.len() <= scale {
+            s.push('0');
+        } else {
+            s.extend(digits[scale..].iter().rev().map(|d| (b'0' + d) as char));
+        }
+        if scale > 0 {
+            s.push('.');
+            s.extend(std::iter::repeat_n('0', scale.saturating_sub(digits.len())));
+            s.extend(digits[..scale.min(digits.len())].iter().rev().map(|d| (b'0' + d) as char));
+        }
+        f.write_str(&s)
+    }
+}
+
+impl Debug for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+// Сравнение по значению: 1.50 == 1.5
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b, _) = self.aligned(other);
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_vec(&a, &b),
+            (true, true) => cmp_vec(&b, &a)
+        }
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Арифметика
+impl Neg for Decimal {
+    type Output = Decimal;
+    fn neg(self) -> Self::Output {
+        let negative = !self.negative && !self.is_zero();
+        Self { negative, ..self }
+    }
+}
+
+// a + b
+impl Add for Decimal {
+    type Output = Decimal;
+    fn add(self, rhs: Self) -> Self::Output {
+        add_decimal(&self, &rhs, rhs.negative)
+    }
+}
+
+impl AddAssign for Decimal {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = add_decimal(self, &rhs, rhs.negative);
+    }
+}
+
+// a - b
+impl Sub for Decimal {
+    type Output = Decimal;
+    fn sub(self, rhs: Self) -> Self::Output {
+        add_decimal(&self, &rhs, !rhs.negative)
+    }
+}
+
+impl SubAssign for Decimal {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = add_decimal(self, &rhs, !rhs.negative);
+    }
+}
+
+// a * b, число знаков после запятой складывается
+impl Mul for Decimal {
+    type Output = Decimal;
+    fn mul(self, rhs: Self) -> Self::Output {
+        mul_decimal(&self, &rhs)
+    }
+}
+
+impl MulAssign for Decimal {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = mul_decimal(self, &rhs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::oper::from_u128;
+    use super::*;
+
+    const MODES: [RoundingMode; 6] = [
+        RoundingMode::HalfEven, RoundingMode::HalfUp, RoundingMode::Down,
+        RoundingMode::Up, RoundingMode::Ceiling, RoundingMode::Floor
+    ];
+
+    fn dec(s: &str) -> Decimal { s.parse().unwrap() }
+
+    // Частное целых a / b с округлением mode
+    fn div_i128(a: i128, b: i128, mode: RoundingMode) -> i128 {
+        let negative = (a < 0) != (b < 0);
+        let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+        let (q, r) = (a / b, a % b);
+        let up = match mode {
+            RoundingMode::HalfEven => 2 * r > b || (2 * r == b && q % 2 == 1),
+            RoundingMode::HalfUp => 2 * r >= b,
+            RoundingMode::Down => false,
+            RoundingMode::Up => r > 0,
+            RoundingMode::Ceiling => r > 0 && !negative,
+            RoundingMode::Floor => r > 0 && negative
+        };
+        let q = (q + up as u128) as i128;
+        if negative { -q } else { q }
+    }
+
+    #[test]
+    fn div_at_ties() {
+        // -1 / 8 = -0.125 и 1 / 8 = 0.125 до двух знаков, 2 / 3 до одного, в порядке MODES
+        let cases = [
+            ("-1", "8", 2, ["-0.12", "-0.13", "-0.12", "-0.13", "-0.12", "-0.13"]),
+            ("1", "8", 2, ["0.12", "0.13", "0.12", "0.13", "0.13", "0.12"]),
+            ("0.375", "-1", 2, ["-0.38", "-0.38", "-0.37", "-0.38", "-0.37", "-0.38"]),
+            ("2", "3", 1, ["0.7", "0.7", "0.6", "0.7", "0.7", "0.6"]),
+            ("1.00", "3.0", 0, ["0", "0", "0", "1", "1", "0"])
+        ];
+        for (x, y, scale, expected) in cases {
+            for (mode, e) in MODES.into_iter().zip(expected) {
+                let q = dec(x).div(&dec(y), scale, mode);
+                assert_eq!(q.to_string(), e, "{x} / {y}, {mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn div_matches_i128() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..5000 {
+            let a: i128 = rng.gen_range(-10i128.pow(12)..10i128.pow(12));
+            let digits = rng.gen_range(1..10);
+            let b: i128 = rng.gen_range(1..10i128.pow(digits)) * if rng.gen() { 1 } else { -1 };
+            let (sa, sb, scale) = (rng.gen_range(0..8), rng.gen_range(0..8), rng.gen_range(0..8));
+            let x = Decimal::new(a < 0, BigDecimal { digits: from_u128(a.unsigned_abs()) }, sa);
+            let y = Decimal::new(b < 0, BigDecimal { digits: from_u128(b.unsigned_abs()) }, sb);
+            for mode in MODES {
+                // a / 10^sa : b / 10^sb = (a * 10^(sb + scale) / (b * 10^sa)) / 10^scale
+                let q = div_i128(a * 10i128.pow(sb + scale), b * 10i128.pow(sa), mode);
+                let expected = Decimal::new(q < 0, BigDecimal { digits: from_u128(q.unsigned_abs()) }, scale);
+                assert_eq!(x.div(&y, scale, mode), expected, "{x} / {y}, {mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn rescale_at_ties() {
+        for (x, expected) in [("2.5", ["2", "3", "2", "3", "3", "2"]), ("-3.5", ["-4", "-4", "-3", "-4", "-3", "-4"])] {
+            for (mode, e) in MODES.into_iter().zip(expected) {
+                assert_eq!(dec(x).rescale(0, mode).to_string(), e, "{x}, {mode:?}");
+            }
+        }
+        assert_eq!(dec("1.5").rescale(3, RoundingMode::Down).to_string(), "1.500");
+    }
+}
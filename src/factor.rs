@@ -0,0 +1,483 @@
+use std::time::{Duration, Instant};
+use rand::Rng;
+use crate::big::BigDecimal;
+use crate::sieve::primes_upto;
+use crate::oper::{cmp_vec, divmod_small, divmod_vec, from_u128, isqrt_vec, sqr};
+use crate::oper::to_bits;
+use crate::modring::{ModRing, Residue};
+use crate::prime::{is_prime, mul_mod_u64, to_u64, Primality};
+
+// Граница пробного деления
+const TRIAL_LIMIT: u64 = 10_000;
+
+// Граница первой стадии метода p - 1
+const PM1_BOUND: u64 = 100_000;
+
+// Число шагов rho-метода для чисел больше 2^64, дальше работает ECM
+const RHO_STEPS: u64 = 50_000;
+
+// Число шагов rho-метода между вычислениями НОД
+const RHO_BATCH: u64 = 128;
+
+// Границы первой стадии ECM и число кривых для каждой из них
+const ECM_BOUNDS: [(u64, usize); 5] = [(2_000, 25), (11_000, 90), (50_000, 300), (250_000, 700), (1_000_000, 1800)];
+
+//-------------------------------------------------------------------------------------------------
+// Ограничение на время и число итераций разложения
+// итерация - шаг rho-метода, простое число в p - 1 или в первой стадии ECM
+#[derive(Clone, Copy, Debug)]
+pub struct FactorBudget {
+    pub time: Option<Duration>,
+    pub iterations: Option<u64>
+}
+
+impl FactorBudget {
+    pub fn unlimited() -> Self {
+        Self { time: None, iterations: None }
+    }
+    pub fn time(time: Duration) -> Self {
+        Self { time: Some(time), iterations: None }
+    }
+    pub fn iterations(iterations: u64) -> Self {
+        Self { time: None, iterations: Some(iterations) }
+    }
+}
+
+// По умолчанию - не больше 30 секунд
+impl Default for FactorBudget {
+    fn default() -> Self { Self::time(Duration::from_secs(30)) }
+}
+
+// Результат разложения
+// factors - простые (или вероятно простые при n >= 2^64) множители с кратностями,
+// unfactored - составные множители, которые не удалось разложить в рамках бюджета
+#[derive(Clone, Debug)]
+pub struct Factorization {
+    pub factors: Vec<(BigDecimal, u32)>,
+    pub unfactored: Vec<(BigDecimal, u32)>
+}
+
+impl Factorization {
+    pub fn is_complete(&self) -> bool { self.unfactored.is_empty() }
+}
+
+// Учет израсходованного бюджета
+struct Meter {
+    start: Instant,
+    time: Option<Duration>,
+    left: Option<u64>
+}
+
+impl Meter {
+    fn new(budget: &FactorBudget) -> Self {
+        Self { start: Instant::now(), time: budget.time, left: budget.iterations }
+    }
+    // учесть k итераций, false - бюджет исчерпан
+    fn spend(&mut self, k: u64) -> bool {
+        if let Some(left) = &mut self.left {
+            if *left < k {
+                *left = 0;
+                return false
+            }
+            *left -= k;
+        }
+        self.time.is_none_or(|t| self.start.elapsed() < t)
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Полное разложение на простые множители с кратностями, по возрастанию
+// без ограничения бюджета - для трудных чисел может работать очень долго
+// (частичное разложение с ограничением - factor_with_budget)
+pub fn factor(n: &BigDecimal) -> Vec<(BigDecimal, u32)> {
+    factor_with_budget(n, &FactorBudget::unlimited()).factors
+}
+
+// Разложение на множители: пробное деление, затем для каждого составного множителя
+// rho-метод Полларда (вариант Брента), метод p - 1 и первая стадия ECM
+// составные множители, оставшиеся после исчерпания бюджета, - в unfactored
+pub fn factor_with_budget(n: &BigDecimal, budget: &FactorBudget) -> Factorization {
+    if n.is_zero() { panic!("factorization of zero"); }
+    let mut meter = Meter::new(budget);
+    let mut primes = Vec::new();
+    let mut rest = Vec::new();
+    let mut m = n.digits.clone();
+    for p in primes_upto(TRIAL_LIMIT) {
+        loop {
+            let (q, r) = divmod_small(&m, p);
+            if r != 0 { break }
+            primes.push(from_u128(p as u128));
+            m = q;
+        }
+        // m < p^2 - m простое или 1
+        if to_u64(&m).is_some_and(|x| x / p < p) { break }
+    }
+    let mut stack = if m == [1u8] { vec![] } else { vec![m] };
+    while let Some(m) = stack.pop() {
+        if to_u64(&m).is_some_and(|x| x < TRIAL_LIMIT * TRIAL_LIMIT) || is_prime(&BigDecimal { digits: m.clone() }) != Primality::Composite {
+            primes.push(m);
+            continue
+        }
+        match split(&m, &mut meter) {
+            Some(d) => {
+                let q = divmod_vec(&m, &d).0;
+                stack.push(d);
+                stack.push(q);
+            }
+            None => rest.push(m)
+        }
+    }
+    Factorization { factors: group(primes), unfactored: group(rest) }
+}
+
+// Одинаковые множители собираем с кратностями
+fn group(mut v: Vec<Vec<u8>>) -> Vec<(BigDecimal, u32)> {
+    v.sort_by(|a, b| cmp_vec(a, b));
+    let mut res: Vec<(BigDecimal, u32)> = Vec::new();
+    for x in v {
+        match res.last_mut() {
+            Some((y, k)) if y.digits == x => *k += 1,
+            _ => res.push((BigDecimal { digits: x }, 1))
+        }
+    }
+    res
+}
+
+// Нетривиальный делитель составного m или None, если бюджет исчерпан
+fn split(m: &[u8], meter: &mut Meter) -> Option<Vec<u8>> {
+    let r = isqrt_vec(m);
+    if sqr(&r) == m { return Some(r) }
+    if let Some(x) = to_u64(m) {
+        return rho_u64(x, meter).map(|d| from_u128(d as u128))
+    }
+    pm1(m, PM1_BOUND, meter)
+        .or_else(|| rho(m, RHO_STEPS, meter))
+        .or_else(|| ecm(m, meter))
+}
+
+//-------------------------------------------------------------------------------------------------
+// rho-метод Полларда в варианте Брента: x -> x^2 + c, c = 1, 2, ...
+
+fn rho_u64(n: u64, meter: &mut Meter) -> Option<u64> {
+    let gcd = |mut a: u64, mut b: u64| {
+        while b != 0 { (a, b) = (b, a % b); }
+        a
+    };
+    for c in 1..n {
+        let f = |x: u64| ((mul_mod_u64(x, x, n) as u128 + c as u128) % n as u128) as u64;
+        let (mut x, mut y, mut ys) = (0, 2, 2);
+        let (mut r, mut q, mut g) = (1u64, 1u64, 1u64);
+        while g == 1 {
+            x = y;
+            for _ in 0..r { y = f(y); }
+            let mut k = 0;
+            while k < r && g == 1 {
+                ys = y;
+                let steps = RHO_BATCH.min(r - k);
+                for _ in 0..steps {
+                    y = f(y);
+                    q = mul_mod_u64(q, x.abs_diff(y), n);
+                }
+                if !meter.spend(steps) { return None }
+                g = gcd(q, n);
+                k += steps;
+            }
+            r *= 2;
+        }
+        // общий множитель набран за один раз - повторяем по шагу
+        if g == n {
+            loop {
+                ys = f(ys);
+                if !meter.spend(1) { return None }
+                g = gcd(x.abs_diff(ys), n);
+                if g > 1 { break }
+            }
+        }
+        if g != n { return Some(g) }
+    }
+    None
+}
+
+fn rho(n: &[u8], limit: u64, meter: &mut Meter) -> Option<Vec<u8>> {
+    let ring = ModRing::new(&BigDecimal { digits: n.to_vec() });
+    let small = |x: u64| ring.residue(&BigDecimal { digits: from_u128(x as u128) });
+    let one = vec![1u8];
+    let mut total = 0;
+    for c in 1u64.. {
+        let c = small(c);
+        let f = |x: &Residue| ring.add(&ring.sqr(x), &c);
+        let (mut x, mut y, mut ys) = (ring.zero(), small(2), small(2));
+        let (mut r, mut q, mut g) = (1u64, ring.one(), one.clone());
+        while g == one {
+            if total >= limit { return None }
+            x = y.clone();
+            for _ in 0..r { y = f(&y); }
+            let mut k = 0;
+            while k < r && g == one {
+                ys = y.clone();
+                let steps = RHO_BATCH.min(r - k);
+                for _ in 0..steps {
+                    y = f(&y);
+                    q = ring.mul(&q, &ring.sub(&x, &y));
+                }
+                if !meter.spend(steps) { return None }
+                g = ring.gcd(&q).digits;
+                k += steps;
+            }
+            total += 2 * r;
+            r *= 2;
+        }
+        if g == n {
+            loop {
+                ys = f(&ys);
+                if !meter.spend(1) { return None }
+                g = ring.gcd(&ring.sub(&x, &ys)).digits;
+                if g != one { break }
+            }
+        }
+        if g != n { return Some(g) }
+    }
+    None
+}
+
+//-------------------------------------------------------------------------------------------------
+// Метод p - 1 Полларда (первая стадия): a = 2^(произведение степеней простых <= bound)
+// находит p, для которого p - 1 раскладывается на простые не больше bound
+fn pm1(n: &[u8], bound: u64, meter: &mut Meter) -> Option<Vec<u8>> {
+    let ring = ModRing::new(&BigDecimal { digits: n.to_vec() });
+    let primes = primes_upto(bound);
+    let one = ring.one();
+    let mut a = ring.add(&one, &one);
+    for (i, &p) in primes.iter().enumerate() {
+        a = ring.pow_bits(&a, &to_bits(&from_u128(prime_power(p, bound) as u128)));
+        if !meter.spend(1) { return None }
+        if i % 64 == 63 || i == primes.len() - 1 {
+            let g = ring.gcd(&ring.sub(&a, &one)).digits;
+            if g == n { return None }
+            if g != [1u8] { return Some(g) }
+        }
+    }
+    None
+}
+
+// Наибольшая степень p, не превосходящая bound
+fn prime_power(p: u64, bound: u64) -> u64 {
+    let mut pe = p;
+    while pe <= bound / p { pe *= p; }
+    pe
+}
+
+//-------------------------------------------------------------------------------------------------
+// Метод эллиптических кривых Ленстры (первая стадия)
+// кривые Монтгомери By^2 = x^3 + Ax^2 + x с параметризацией Суямы,
+// точки в проективных координатах (X : Z), (A + 2) / 4 = a24n / a24d
+fn ecm(n: &[u8], meter: &mut Meter) -> Option<Vec<u8>> {
+    let mut rng = rand::thread_rng();
+    let mut curves = 0;
+    let mut primes = Vec::new();
+    let mut bound = 0;
+    loop {
+        // граница растет с числом испробованных кривых
+        let mut total = 0;
+        let b1 = ECM_BOUNDS.iter()
+            .find(|(_, k)| { total += k; curves < total })
+            .unwrap_or(&ECM_BOUNDS[ECM_BOUNDS.len() - 1]).0;
+        if b1 != bound {
+            bound = b1;
+            primes = primes_upto(bound);
+        }
+        let sigma = rng.gen_range(6..1u64 << 32);
+        if let Some(d) = ecm_curve(n, sigma, bound, &primes, meter)? { return Some(d) }
+        curves += 1;
+    }
+}
+
+// Одна кривая: Some(None) - делитель не найден, None - бюджет исчерпан
+fn ecm_curve(n: &[u8], sigma: u64, bound: u64, primes: &[u64], meter: &mut Meter) -> Option<Option<Vec<u8>>> {
+    let ring = ModRing::new(&BigDecimal { digits: n.to_vec() });
+    let small = |x: u128| ring.residue(&BigDecimal { digits: from_u128(x) });
+    // u = sigma^2 - 5, v = 4 sigma, P = (u^3 : v^3)
+    let u = small(sigma as u128 * sigma as u128 - 5);
+    let v = small(4 * sigma as u128);
+    let u3 = ring.mul(&ring.sqr(&u), &u);
+    let v3 = ring.mul(&ring.sqr(&v), &v);
+    // (A + 2) / 4 = (v - u)^3 (3u + v) / (16 u^3 v)
+    let vu = ring.sub(&v, &u);
+    let a24n = ring.mul(&ring.mul(&ring.sqr(&vu), &vu), &ring.add(&ring.mul(&u, &small(3)), &v));
+    let a24d = ring.mul(&ring.mul(&u3, &v), &small(16));
+    let curve = Curve { ring: &ring, a24n, a24d };
+    let mut p = (u3, v3);
+    for &q in primes {
+        p = curve.ladder(prime_power(q, bound), &p);
+        if !meter.spend(1) { return None }
+    }
+    let g = ring.gcd(&p.1).digits;
+    Some(if g != [1u8] && g != n { Some(g) } else { None })
+}
+
+type Point = (Residue, Residue);
+
+struct Curve<'a> {
+    ring: &'a ModRing,
+    a24n: Residue,
+    a24d: Residue
+}
+
+impl Curve<'_> {
+    // 2P
+    fn double(&self, p: &Point) -> Point {
+        let r = self.ring;
+        let t1 = r.sqr(&r.add(&p.0, &p.1));
+        let t2 = r.sqr(&r.sub(&p.0, &p.1));
+        // t = 4XZ
+        let t = r.sub(&t1, &t2);
+        let x = r.mul(&r.mul(&t1, &t2), &self.a24d);
+        let z = r.mul(&t, &r.add(&r.mul(&t2, &self.a24d), &r.mul(&t, &self.a24n)));
+        (x, z)
+    }
+    // P + Q по известной разности d = P - Q
+    fn add(&self, p: &Point, q: &Point, d: &Point) -> Point {
+        let r = self.ring;
+        let u = r.mul(&r.sub(&p.0, &p.1), &r.add(&q.0, &q.1));
+        let w = r.mul(&r.add(&p.0, &p.1), &r.sub(&q.0, &q.1));
+        let x = r.mul(&d.1, &r.sqr(&r.add(&u, &w)));
+        let z = r.mul(&d.0, &r.sqr(&r.sub(&u, &w)));
+        (x, z)
+    }
+    // kP, лесенка Монтгомери
+    fn ladder(&self, k: u64, p: &Point) -> Point {
+        let mut r0 = p.clone();
+        let mut r1 = self.double(p);
+        for i in (0..63 - k.leading_zeros()).rev() {
+            if (k >> i) & 1 == 1 {
+                r0 = self.add(&r1, &r0, p);
+                r1 = self.double(&r1);
+            } else {
+                r1 = self.add(&r1, &r0, p);
+                r0 = self.double(&r0);
+            }
+        }
+        r0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    // Произведение множителей с кратностями
+    fn product_of(factors: &[(BigDecimal, u32)]) -> BigDecimal {
+        let mut res = BigDecimal::one();
+        for (p, k) in factors {
+            for _ in 0..*k { res = &res * p; }
+        }
+        res
+    }
+
+    // Произведение всех множителей, в том числе неразложенных
+    fn product(f: &Factorization) -> BigDecimal {
+        &product_of(&f.factors) * &product_of(&f.unfactored)
+    }
+
+    // Множители простые, различные, по возрастанию, их произведение равно n
+    fn check_factors(n: &BigDecimal, factors: &[(BigDecimal, u32)]) {
+        assert_eq!(&product_of(factors), n);
+        assert!(factors.windows(2).all(|w| w[0].0 < w[1].0), "{n}: {factors:?}");
+        for (p, _) in factors {
+            assert_ne!(is_prime(p), Primality::Composite, "{n}: {p}");
+        }
+    }
+
+    // Разложение полное
+    fn check_complete(n: &BigDecimal, f: &Factorization) {
+        assert!(f.is_complete(), "{n}: {f:?}");
+        check_factors(n, &f.factors);
+    }
+
+    // Делитель d нетривиален и делит n
+    fn check_divisor(n: &str, d: Option<Vec<u8>>) {
+        let d = d.unwrap_or_else(|| panic!("{n}: no divisor"));
+        let (n, d) = (big(n), BigDecimal { digits: d });
+        assert!(d > BigDecimal::one() && d < n, "{n}: {d}");
+        assert!(n.divmod(&d).1.is_zero(), "{n}: {d}");
+    }
+
+    #[test]
+    fn factor_small() {
+        for n in 1..5000u64 {
+            let n = BigDecimal { digits: from_u128(n as u128) };
+            check_factors(&n, &factor(&n));
+        }
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..200 {
+            let n: u64 = rng.gen::<u64>() >> rng.gen_range(0..32);
+            let n = BigDecimal { digits: from_u128(n.max(1) as u128) };
+            check_factors(&n, &factor(&n));
+        }
+    }
+
+    #[test]
+    fn factor_with_budget_semiprimes() {
+        // 2^3 * 3^2 * 101^2 * 4616410045279 * 1083251312599229: пробное деление и p - 1
+        let n = &big("734472") * &big("5000732241044742931070489891");
+        let f = factor_with_budget(&n, &FactorBudget::unlimited());
+        check_complete(&n, &f);
+        assert_eq!(f.factors.last().unwrap(), &(big("1083251312599229"), 1));
+        // точный квадрат
+        let n = &big("100012351") * &big("100012351");
+        let f = factor_with_budget(&n, &FactorBudget::unlimited());
+        assert_eq!(f.factors, vec![(big("100012351"), 2)]);
+        assert_eq!(factor(&n), f.factors);
+        assert_eq!(factor(&big("360")), vec![(big("2"), 3), (big("3"), 2), (big("5"), 1)]);
+        assert_eq!(factor(&BigDecimal::one()), vec![]);
+    }
+
+    #[test]
+    fn rho_semiprimes() {
+        // 2147483659 * 4294967311 < 2^64
+        let mut meter = Meter::new(&FactorBudget::unlimited());
+        let d = rho_u64(9223372116311670949, &mut meter).unwrap();
+        assert!(d == 2147483659 || d == 4294967311);
+        // 1035019 * 103273360101803
+        let n = "106889889899208039257";
+        check_divisor(n, rho(&big(n).digits, RHO_STEPS, &mut meter));
+    }
+
+    #[test]
+    fn pm1_semiprime() {
+        // p - 1 = 2 * 3 * 41 * 43 * 53 * 131 * 239 * 263, у q - 1 есть делитель 270812828149807
+        let n = "5000732241044742931070489891";
+        let mut meter = Meter::new(&FactorBudget::unlimited());
+        let d = pm1(&big(n).digits, 1000, &mut meter);
+        assert_eq!(d, Some(big("4616410045279").digits));
+    }
+
+    #[test]
+    fn ecm_semiprime() {
+        // 3485563 * 1052538673704071, у p - 1 есть делитель 580927
+        let n = "3668689857131982826973";
+        let mut meter = Meter::new(&FactorBudget::unlimited());
+        check_divisor(n, ecm(&big(n).digits, &mut meter));
+    }
+
+    #[test]
+    fn budget_exhausted() {
+        // произведение двух 20-значных простых за 100 итераций не разложить
+        let hard = big("1094692120369434203414084908364039217331");
+        let f = factor_with_budget(&hard, &FactorBudget::iterations(100));
+        assert!(f.factors.is_empty());
+        assert_eq!(f.unfactored, vec![(hard.clone(), 1)]);
+        // малые множители выделяются пробным делением и без бюджета
+        let n = &big("734472") * &hard;
+        let f = factor_with_budget(&n, &FactorBudget::iterations(0));
+        assert_eq!(f.unfactored, vec![(hard, 1)]);
+        assert_eq!(product(&f), n);
+        let f = factor_with_budget(&n, &FactorBudget::time(Duration::ZERO));
+        assert!(!f.is_complete());
+        assert_eq!(product(&f), n);
+    }
+}
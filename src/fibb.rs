@@ -1,7 +1,9 @@
 use std::fmt::{Display, Formatter};
-use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::ops::{Add, AddAssign, Mul, MulAssign, Range};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use crate::big::BigDecimal;
-use crate::oper::{add_vec, mul_vec};
+use crate::complex::Complex;
+use crate::oper::{add_vec, cmp_vec, divmod_vec, mul_small, mul_vec, sqr, sub_vec};
 
 //-------------------------------------------------------------------------------------------------
 // Матрицы больших чисел 2х2
@@ -24,17 +26,17 @@ impl Mat {
     fn sqr(&self) -> Self {
         // (a, b) (a, b) = (a^2+bc,  b*(a+d)
         // (c, d) (c, d)   (c*(a+d), bc+d^2)
-        let ad= add_vec(&self.a.digits,&self.d.digits);
-        let bc= mul_vec(&self.b.digits,&self.c.digits);
+        let ad = &self.a + &self.d;
+        let bc = &self.b * &self.c;
         Self {
-            a: BigDecimal::from(add_vec(&self.a.sqr().digits,&bc).as_slice()),
-            b: BigDecimal::from(mul_vec(&self.b.digits,&ad).as_slice()),
-            c: BigDecimal::from(mul_vec(&self.c.digits,&ad).as_slice()),
-            d: BigDecimal::from(add_vec(&self.d.sqr().digits,&bc).as_slice())
+            a: self.a.sqr() + &bc,
+            b: &self.b * &ad,
+            c: &self.c * &ad,
+            d: self.d.sqr() + &bc
         }
     }
     // степень матрицы
-    fn exp(&mut self, p: u32) { *self = pow(&self, p) }
+    fn exp(&mut self, p: u64) { *self = pow(&self, p) }
 }
 
 impl Add for Mat {
@@ -70,14 +72,81 @@ impl Display for Mat {
 }
 
 // Вычисление степени матрицы по определению
-fn pow(mat: &Mat, m: u32) -> Mat {
+fn pow(mat: &Mat, m: u64) -> Mat {
     if m == 0 { return Mat::one() }
     let x = pow(mat, m >> 1).sqr();
     if m & 1 == 0 { x } else { mul_mat(mat, &x) }
 }
 
-// Вычисление n-го числа Фибоначии
-pub fn fib(n: u32) -> BigDecimal {
+// Вычисление n-го числа Фибоначии, F(0) = 0, F(1) = 1
+// паника FibError::TooLarge, если F(n) слишком велико для умножения через БПФ
+// (проверка без паники и с ограничением памяти - try_fib)
+pub fn fib(n: u64) -> BigDecimal {
+    fib_pair(n).0
+}
+
+// Пара (F(n), F(n+1)) методом удвоения, паника - как у fib
+pub fn fib_pair(n: u64) -> (BigDecimal, BigDecimal) {
+    if let Err(e) = fft_size("fib", n, LOG_PHI) { panic!("{e}"); }
+    let (a, b) = doubling(n, 1, None);
+    (BigDecimal { digits: a }, BigDecimal { digits: b })
+}
+
+// n-е число Люка: L(n) = 2F(n+1) - F(n), L(0) = 2, L(1) = 1
+// паника - как у fib (без паники - try_lucas)
+pub fn lucas(n: u64) -> BigDecimal {
+    if let Err(e) = fft_size("lucas", n, LOG_PHI) { panic!("{e}"); }
+    let (a, b) = doubling(n, 1, None);
+    BigDecimal { digits: sub_vec(&add_vec(&b, &b), &a) }
+}
+
+// n-е число Пелля: P(n) = 2P(n-1) + P(n-2), P(0) = 0, P(1) = 1
+// паника - как у fib (без паники - try_pell)
+pub fn pell(n: u64) -> BigDecimal {
+    if let Err(e) = fft_size("pell", n, LOG_SILVER) { panic!("{e}"); }
+    BigDecimal { digits: doubling(n, 2, None).0 }
+}
+
+// F(n) mod m
+pub fn fib_mod(n: u64, m: &BigDecimal) -> BigDecimal {
+    if m.is_zero() { panic!("zero modulus"); }
+    BigDecimal { digits: doubling(n, 1, Some(&m.digits)).0 }
+}
+
+// Пара (x(n), x(n+1)) для x(k+1) = t x(k) + x(k-1), x(0) = 0, x(1) = 1 методом удвоения
+// (по модулю m, если он задан):
+// x(2k) = x(k) * (2x(k+1) - t x(k)), x(2k+1) = x(k)^2 + x(k+1)^2
+// t = 1 дает числа Фибоначчи, t = 2 - числа Пелля
+fn doubling(n: u64, t: u64, m: Option<&[u8]>) -> (Vec<u8>, Vec<u8>) {
+    let reduce = |x: Vec<u8>| match m {
+        Some(m) => divmod_vec(&x, m).1,
+        None => x
+    };
+    // (a, b) = (x(k), x(k+1)), k - старшие биты n
+    let mut a = vec![0u8];
+    let mut b = reduce(vec![1u8]);
+    for i in (0..u64::BITS - n.leading_zeros()).rev() {
+        let ta = mul_small(&a, t);
+        let s = match m {
+            // -t x(k) = t (m - x(k)) по модулю m
+            Some(m) => add_vec(&add_vec(&b, &b), &sub_vec(&mul_small(m, t), &ta)),
+            None => sub_vec(&add_vec(&b, &b), &ta)
+        };
+        let c = reduce(mul_vec(&a, &s));
+        let d = reduce(add_vec(&sqr(&a), &sqr(&b)));
+        if (n >> i) & 1 == 0 {
+            (a, b) = (c, d);
+        } else {
+            b = reduce(add_vec(&mul_small(&d, t), &c));
+            a = d;
+        }
+    }
+    (a, b)
+}
+
+// n-е число Фибоначчи через степень матрицы (для проверки)
+pub fn fib_matrix(n: u64) -> BigDecimal {
+    if n == 0 { return BigDecimal::zero() }
     let mut f = Mat {
         a: BigDecimal::one(), b: BigDecimal::one(),
         c: BigDecimal::one(), d: BigDecimal::zero()
@@ -86,13 +155,183 @@ pub fn fib(n: u32) -> BigDecimal {
     f.a
 }
 
+//-------------------------------------------------------------------------------------------------
+// Последовательные числа Фибоначчи F(a), F(a+1), ..., F(b-1)
+// первое вычисляется удвоением, остальные - сложением двух предыдущих
+pub struct FibRange {
+    n: u64,
+    end: u64,
+    a: Vec<u8>,
+    b: Vec<u8>
+}
+
+pub fn fib_range(range: Range<u64>) -> FibRange {
+    let (a, b) = if range.is_empty() {
+        (vec![0u8], vec![1u8])
+    } else {
+        let (a, b) = fib_pair(range.start);
+        (a.digits, b.digits)
+    };
+    FibRange { n: range.start, end: range.end, a, b }
+}
+
+impl Iterator for FibRange {
+    type Item = BigDecimal;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.n >= self.end { return None }
+        self.n += 1;
+        // (a, b) -> (b, a + b)
+        let c = add_vec(&self.a, &self.b);
+        let a = std::mem::replace(&mut self.b, c);
+        Some(BigDecimal { digits: std::mem::replace(&mut self.a, a) })
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.end.saturating_sub(self.n) as usize;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for FibRange {}
+
+//-------------------------------------------------------------------------------------------------
+// Период Пизано: период последовательности F(n) mod m
+// перебор, не больше 6m шагов - только для небольших m
+pub fn pisano(m: u64) -> u64 {
+    if m == 0 { panic!("zero modulus"); }
+    if m == 1 { return 1 }
+    let m = m as u128;
+    let (mut a, mut b) = (0u128, 1u128);
+    let mut n = 0u64;
+    loop {
+        (a, b) = (b, (a + b) % m);
+        n += 1;
+        if a == 0 && b == 1 { return n }
+    }
+}
+
+// Представление Цекендорфа: x как сумма несоседних чисел Фибоначчи
+// возвращает индексы k >= 2 слагаемых F(k) по убыванию, для нуля - пустой вектор
+pub fn zeckendorf(x: &BigDecimal) -> Vec<u64> {
+    let mut res = Vec::new();
+    if x.is_zero() { return res }
+    let mut x = x.digits.clone();
+    // оценка k по F(k) ~ phi^k / sqrt(5)
+    let lead: String = x.iter().rev().take(15).map(|d| (b'0' + d) as char).collect();
+    let lg = lead.parse::<f64>().unwrap().log10() + (x.len() - lead.len()) as f64;
+    let mut k = ((lg + 0.3494850021680094) / LOG_PHI) as u64;
+    let (a, b) = fib_pair(k);
+    let (mut a, mut b) = (a.digits, b.digits);
+    // уточняем k до F(k) <= x < F(k+1)
+    while cmp_vec(&b, &x) != std::cmp::Ordering::Greater {
+        (a, b) = (b.clone(), add_vec(&a, &b));
+        k += 1;
+    }
+    while cmp_vec(&a, &x) == std::cmp::Ordering::Greater {
+        (a, b) = (sub_vec(&b, &a), a);
+        k -= 1;
+    }
+    // жадно вычитаем наибольшее число Фибоначчи, спускаясь по парам (F(k), F(k+1))
+    while x != [0u8] {
+        if cmp_vec(&a, &x) != std::cmp::Ordering::Greater {
+            x = sub_vec(&x, &a);
+            res.push(k);
+        }
+        (a, b) = (sub_vec(&b, &a), a);
+        k -= 1;
+    }
+    res
+}
+
+//-------------------------------------------------------------------------------------------------
+// Ограничение памяти для try_fib, try_lucas и try_pell (в байтах), по умолчанию 1 Гб
+static FIB_BUDGET: AtomicUsize = AtomicUsize::new(1 << 30);
+
+pub fn set_fib_budget(bytes: usize) { FIB_BUDGET.store(bytes, Ordering::Relaxed) }
+
+pub fn fib_budget() -> usize { FIB_BUDGET.load(Ordering::Relaxed) }
+
+// Наибольший размер БПФ, для которого есть таблица в REV_CASH
+const MAX_FFT: usize = 1 << 22;
+
+// Ошибка try_fib, try_lucas и try_pell, seq - имя последовательности
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FibError {
+    // вычисление потребует больше памяти, чем разрешено
+    OutOfBudget { seq: &'static str, n: u64, required: usize, budget: usize },
+    // результат слишком велик для умножения через БПФ
+    TooLarge { seq: &'static str, n: u64, digits: usize }
+}
+
+impl Display for FibError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FibError::OutOfBudget { seq, n, required, budget } =>
+                write!(f, "{seq}({n}) needs about {required} bytes, budget is {budget} bytes"),
+            FibError::TooLarge { seq, n, digits } =>
+                write!(f, "{seq}({n}) has about {digits} digits, too many to multiply")
+        }
+    }
+}
+
+impl std::error::Error for FibError {}
+
+// log10 скорости роста: phi = (1 + sqrt(5)) / 2 для Фибоначчи и Люка, 1 + sqrt(2) для Пелля
+const LOG_PHI: f64 = 0.20898764024997873;
+const LOG_SILVER: f64 = 0.38277568533786305;
+
+// Число десятичных цифр x(n): n * lg + 1 (с запасом)
+fn seq_digits(n: u64, lg: f64) -> usize {
+    ((n as f64 * lg) as usize).saturating_add(1)
+}
+
+// F(n) с проверкой размера результата и требуемой памяти
+pub fn try_fib(n: u64) -> Result<BigDecimal, FibError> {
+    try_seq("fib", n, LOG_PHI)?;
+    Ok(fib(n))
+}
+
+// L(n) с проверкой, как у try_fib
+pub fn try_lucas(n: u64) -> Result<BigDecimal, FibError> {
+    try_seq("lucas", n, LOG_PHI)?;
+    Ok(lucas(n))
+}
+
+// P(n) с проверкой, как у try_fib
+pub fn try_pell(n: u64) -> Result<BigDecimal, FibError> {
+    try_seq("pell", n, LOG_SILVER)?;
+    Ok(pell(n))
+}
+
+// Проверка размера БПФ и памяти для x(n)
+fn try_seq(seq: &'static str, n: u64, lg: f64) -> Result<(), FibError> {
+    let (size, digits) = fft_size(seq, n, lg)?;
+    // три массива комплексных чисел в БПФ и шесть векторов цифр
+    let required = 3 * size * size_of::<Complex>() + 6 * digits;
+    let budget = fib_budget();
+    if required > budget {
+        return Err(FibError::OutOfBudget { seq, n, required, budget })
+    }
+    Ok(())
+}
+
+// Размер БПФ для вычисления x(n) и число цифр x(n + 1)
+fn fft_size(seq: &'static str, n: u64, lg: f64) -> Result<(usize, usize), FibError> {
+    let digits = seq_digits(n.saturating_add(1), lg);
+    // последнее удвоение умножает числа из digits / 2 цифр
+    let size = (digits / 2 + 1).checked_next_power_of_two().map_or(usize::MAX, |x| x.saturating_mul(2));
+    if size > MAX_FFT {
+        return Err(FibError::TooLarge { seq, n, digits })
+    }
+    Ok((size, digits))
+}
+
 // p + q
 fn add_mat(p: &Mat, q: &Mat) -> Mat {
     Mat {
-        a: BigDecimal::from(add_vec(&p.a.digits,&q.a.digits).as_slice()),
-        b: BigDecimal::from(add_vec(&p.b.digits,&q.b.digits).as_slice()),
-        c: BigDecimal::from(add_vec(&p.c.digits,&q.c.digits).as_slice()),
-        d: BigDecimal::from(add_vec(&p.d.digits,&q.d.digits).as_slice())
+        a: &p.a + &q.a,
+        b: &p.b + &q.b,
+        c: &p.c + &q.c,
+        d: &p.d + &q.d
     }
 }
 
@@ -101,21 +340,135 @@ fn mul_mat(p: &Mat, q: &Mat) -> Mat {
     // (a, b) (a, b) = (aa+bc, ab+bd)
     // (c, d) (c, d)   (ca+dc, cb+dd)
     Mat {
-        a: BigDecimal::from(add_vec(
-            &mul_vec(&p.a.digits,&q.a.digits),
-            &mul_vec(&p.b.digits,&q.c.digits)
-        ).as_slice()),
-        b: BigDecimal::from(add_vec(
-            &mul_vec(&p.a.digits,&q.b.digits),
-            &mul_vec(&p.b.digits,&q.d.digits)
-        ).as_slice()),
-        c: BigDecimal::from(add_vec(
-            &mul_vec(&p.c.digits,&q.a.digits),
-            &mul_vec(&p.d.digits,&q.c.digits)
-        ).as_slice()),
-        d: BigDecimal::from(add_vec(
-            &mul_vec(&p.c.digits,&q.b.digits),
-            &mul_vec(&p.d.digits,&q.d.digits)
-        ).as_slice())
-    }
-}
\ No newline at end of file
+        a: &p.a * &q.a + &p.b * &q.c,
+        b: &p.a * &q.b + &p.b * &q.d,
+        c: &p.c * &q.a + &p.d * &q.c,
+        d: &p.c * &q.b + &p.d * &q.d
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    #[test]
+    fn fib_known() {
+        assert_eq!(fib(0), BigDecimal::zero());
+        assert_eq!(fib(1), BigDecimal::one());
+        assert_eq!(fib(2), BigDecimal::one());
+        assert_eq!(fib(10), big("55"));
+        assert_eq!(fib(100), big("354224848179261915075"));
+        assert_eq!(fib_pair(0), (BigDecimal::zero(), BigDecimal::one()));
+        // сложение подряд, матрица и удвоение
+        let mut a = BigDecimal::zero();
+        let mut b = BigDecimal::one();
+        for n in 0..300u64 {
+            assert_eq!(fib(n), a, "n = {n}");
+            (a, b) = (b.clone(), &a + &b);
+        }
+        for n in [1000u64, 4097, 12345] {
+            assert_eq!(fib(n), fib_matrix(n), "n = {n}");
+        }
+    }
+
+    #[test]
+    fn lucas_pell_known() {
+        assert_eq!(lucas(0), big("2"));
+        assert_eq!(lucas(1), BigDecimal::one());
+        assert_eq!(lucas(10), big("123"));
+        assert_eq!(lucas(100), big("792070839848372253127"));
+        assert_eq!(pell(0), BigDecimal::zero());
+        assert_eq!(pell(1), BigDecimal::one());
+        assert_eq!(pell(10), big("2378"));
+        assert_eq!(pell(100), big("66992092050551637663438906713182313772"));
+        // L(n) = F(n-1) + F(n+1), P(n) = 2P(n-1) + P(n-2)
+        for n in 1..200u64 {
+            assert_eq!(lucas(n), &fib(n - 1) + &fib(n + 1), "n = {n}");
+        }
+        for n in 2..200u64 {
+            assert_eq!(pell(n), &(&pell(n - 1) + &pell(n - 1)) + &pell(n - 2), "n = {n}");
+        }
+    }
+
+    #[test]
+    fn fib_mod_known() {
+        let m = big("100000000000000000000");
+        assert_eq!(fib_mod(1000, &m), big("76137795166849228875"));
+        assert_eq!(fib_mod(0, &m), BigDecimal::zero());
+        assert_eq!(fib_mod(12345, &BigDecimal::one()), BigDecimal::zero());
+    }
+
+    #[test]
+    fn fib_range_matches_fib() {
+        let v: Vec<BigDecimal> = fib_range(95..105).collect();
+        assert_eq!(v.len(), 10);
+        for (n, x) in (95..105).zip(&v) {
+            assert_eq!(*x, fib(n), "n = {n}");
+        }
+        let v: Vec<BigDecimal> = fib_range(0..3).collect();
+        assert_eq!(v, vec![BigDecimal::zero(), BigDecimal::one(), BigDecimal::one()]);
+        // пустой диапазон
+        assert_eq!(fib_range(5..5).next(), None);
+        let (start, end) = (7, 5);
+        assert_eq!(fib_range(start..end).size_hint(), (0, Some(0)));
+        let mut r = fib_range(10..20);
+        assert_eq!(r.len(), 10);
+        r.next();
+        assert_eq!(r.size_hint(), (9, Some(9)));
+    }
+
+    #[test]
+    fn try_fib_errors() {
+        // индексы за пределами u32: слишком много цифр для БПФ
+        for n in [1u64 << 33, u64::MAX] {
+            assert!(matches!(try_fib(n), Err(FibError::TooLarge { seq: "fib", .. })));
+            assert!(matches!(try_lucas(n), Err(FibError::TooLarge { seq: "lucas", .. })));
+            assert!(matches!(try_pell(n), Err(FibError::TooLarge { seq: "pell", .. })));
+        }
+        // числа Пелля растут быстрее: для того же n БПФ не хватает только им
+        let n = 15_000_000;
+        assert!(fft_size("fib", n, LOG_PHI).is_ok());
+        assert!(matches!(fft_size("pell", n, LOG_SILVER), Err(FibError::TooLarge { .. })));
+        // ограничение памяти, глобальное - восстанавливаем после проверки
+        let old = fib_budget();
+        set_fib_budget(1000);
+        let res = try_fib(1000);
+        let res_pell = try_pell(1000);
+        let res_small = try_fib(10);
+        set_fib_budget(old);
+        match res {
+            Err(FibError::OutOfBudget { seq: "fib", n: 1000, required, budget: 1000 }) => assert!(required > 1000),
+            other => panic!("unexpected {other:?}")
+        }
+        assert!(matches!(res_pell, Err(FibError::OutOfBudget { seq: "pell", .. })));
+        // маленькому числу хватает и такого ограничения
+        assert_eq!(res_small, Ok(big("55")));
+        assert_eq!(try_fib(100), Ok(big("354224848179261915075")));
+        assert_eq!(try_lucas(10), Ok(big("123")));
+        assert_eq!(try_pell(10), Ok(big("2378")));
+    }
+
+    #[test]
+    #[should_panic(expected = "too many to multiply")]
+    fn fib_huge_panics() {
+        fib(u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "pell(")]
+    fn pell_huge_panics() {
+        pell(1 << 40);
+    }
+
+    #[test]
+    fn pisano_zeckendorf() {
+        assert_eq!(pisano(1), 1);
+        assert_eq!(pisano(2), 3);
+        assert_eq!(pisano(10), 60);
+        assert_eq!(zeckendorf(&BigDecimal::zero()), Vec::<u64>::new());
+        // 100 = 89 + 8 + 3
+        assert_eq!(zeckendorf(&big("100")), vec![11, 6, 4]);
+    }
+}
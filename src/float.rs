@@ -0,0 +1,557 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+use crate::big::BigDecimal;
+use crate::oper::{add_small_vec, add_vec, cmp_vec, divmod_vec, isqrt_vec, mul_vec, shl10, sub_vec, trim_vec};
+
+// Точность по умолчанию (десятичных знаков)
+pub const DEFAULT_PRECISION: usize = 50;
+
+//-------------------------------------------------------------------------------------------------
+// Режимы округления
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RoundingMode {
+    // к ближайшему, при равенстве - к четному
+    HalfEven,
+    // к ближайшему, при равенстве - от нуля
+    HalfUp,
+    // к нулю
+    Down,
+    // от нуля
+    Up,
+    // к +бесконечности
+    Ceiling,
+    // к -бесконечности
+    Floor
+}
+
+impl RoundingMode {
+    // Нужно ли увеличить модуль усеченного числа на единицу младшего разряда
+    // negative - знак числа, odd - последняя оставшаяся цифра нечетная,
+    // first - первая отброшенная цифра, rest - среди остальных отброшенных есть не 0
+    pub fn round_up(&self, negative: bool, odd: bool, first: u8, rest: bool) -> bool {
+        let inexact = first > 0 || rest;
+        match self {
+            RoundingMode::HalfEven => first > 5 || (first == 5 && (rest || odd)),
+            RoundingMode::HalfUp => first >= 5,
+            RoundingMode::Down => false,
+            RoundingMode::Up => inexact,
+            RoundingMode::Ceiling => inexact && !negative,
+            RoundingMode::Floor => inexact && negative
+        }
+    }
+}
+
+// Отбросить k младших цифр числа v с округлением
+pub fn round_digits(v: &[u8], k: usize, negative: bool, mode: RoundingMode) -> Vec<u8> {
+    if k == 0 { return v.to_vec() }
+    if k > v.len() {
+        let rest = v != [0u8].as_slice();
+        return if mode.round_up(negative, false, 0, rest) { vec![1u8] } else { vec![0u8] }
+    }
+    let first = v[k - 1];
+    let rest = v[..k - 1].iter().any(|&x| x != 0);
+    let mut res = if k == v.len() { vec![0u8] } else { v[k..].to_vec() };
+    if mode.round_up(negative, res[0] & 1 == 1, first, rest) {
+        add_small_vec(&mut res, 1);
+    }
+    res
+}
+
+//-------------------------------------------------------------------------------------------------
+// Большие числа с плавающей точкой
+// значение = (-1)^negative * mantissa * 10^exponent,
+// в мантиссе не более precision цифр и нет нулей в младших разрядах
+#[derive(Clone)]
+pub struct BigFloat {
+    negative: bool,
+    mantissa: BigDecimal,
+    exponent: i64,
+    precision: usize,
+    rounding: RoundingMode
+}
+
+impl BigFloat {
+    pub fn zero(precision: usize) -> Self {
+        if precision == 0 { panic!("precision must be positive"); }
+        Self {
+            negative: false,
+            mantissa: BigDecimal::zero(),
+            exponent: 0,
+            precision,
+            rounding: RoundingMode::HalfEven
+        }
+    }
+    pub fn one(precision: usize) -> Self {
+        Self::from_big(BigDecimal::one(), precision)
+    }
+    // целое -> число с плавающей точкой (с округлением до precision цифр)
+    pub fn from_big(x: BigDecimal, precision: usize) -> Self {
+        Self::zero(precision).with_parts(false, x.digits, 0)
+    }
+    // (-1)^negative * m * 10^e
+    pub fn from_parts(negative: bool, m: BigDecimal, e: i64, precision: usize) -> Self {
+        Self::zero(precision).with_parts(negative, m.digits, e)
+    }
+    // строка вида "-123.456e-7" с точностью precision
+    pub fn parse(s: &str, precision: usize) -> Self {
+        let (negative, m, e) = parse_float(s);
+        Self::zero(precision).with_parts(negative, m, e)
+    }
+    pub fn precision(&self) -> usize { self.precision }
+    pub fn rounding(&self) -> RoundingMode { self.rounding }
+    pub fn mantissa(&self) -> &BigDecimal { &self.mantissa }
+    pub fn exponent(&self) -> i64 { self.exponent }
+    pub fn is_zero(&self) -> bool { self.mantissa.is_zero() }
+    pub fn is_negative(&self) -> bool { self.negative }
+    // изменить точность (с округлением)
+    pub fn with_precision(self, precision: usize) -> Self {
+        if precision == 0 { panic!("precision must be positive"); }
+        Self::zero(precision).with_rounding(self.rounding)
+            .with_parts(self.negative, self.mantissa.digits, self.exponent)
+    }
+    // изменить режим округления
+    pub fn with_rounding(self, rounding: RoundingMode) -> Self {
+        Self { rounding, ..self }
+    }
+    // |x|
+    pub fn abs(&self) -> Self {
+        Self { negative: false, ..self.clone() }
+    }
+    // порядок старшей цифры: 10^adjusted <= |x| < 10^(adjusted + 1)
+    pub fn adjusted(&self) -> i64 {
+        self.exponent + self.mantissa.digits.len() as i64 - 1
+    }
+    // квадратный корень
+    pub fn sqrt(&self) -> Self {
+        if self.negative && !self.is_zero() { panic!("square root of a negative number"); }
+        if self.is_zero() { return self.clone() }
+        // m * 10^e = (m * 10^(2s + odd)) * 10^(e - 2s - odd), показатель четный,
+        // корень из мантиссы имеет не менее precision + 2 цифр
+        let m = &self.mantissa.digits;
+        let odd = self.exponent.rem_euclid(2) as usize;
+        let s = (self.precision + 2).saturating_sub(m.len() / 2) + 1;
+        let scaled = shl10(m, 2 * s + odd);
+        let root = isqrt_vec(&scaled);
+        let exact = mul_vec(&root, &root) == scaled;
+        let e = (self.exponent - odd as i64) / 2 - s as i64;
+        self.round_sticky(false, root, e, !exact)
+    }
+    // целая часть (с отбрасыванием дробной)
+    pub fn trunc(&self) -> BigDecimal {
+        if self.exponent >= 0 {
+            BigDecimal { digits: shl10(&self.mantissa.digits, self.exponent as usize) }
+        } else {
+            BigDecimal { digits: round_digits(&self.mantissa.digits, (-self.exponent) as usize, false, RoundingMode::Down) }
+        }
+    }
+    // приближенное значение в f64 (по 20 старшим цифрам)
+    pub fn to_f64(&self) -> f64 {
+        let m = &self.mantissa.digits;
+        let k = m.len().saturating_sub(20);
+        let top: String = m[k..].iter().rev().map(|d| (b'0' + d) as char).collect();
+        let x: f64 = format!("{top}e{}", self.exponent + k as i64).parse().unwrap();
+        if self.negative { -x } else { x }
+    }
+
+    // Сборка числа с округлением мантиссы до текущей точности
+    fn with_parts(self, negative: bool, mut m: Vec<u8>, mut e: i64) -> Self {
+        trim_vec(&mut m);
+        if m.len() > self.precision {
+            let k = m.len() - self.precision;
+            m = round_digits(&m, k, negative, self.rounding);
+            e += k as i64;
+        }
+        // убираем 0 в младших разрядах, в том числе 10^precision после округления
+        let z = m.iter().take_while(|&&x| x == 0).count();
+        if z == m.len() {
+            return Self { negative: false, mantissa: BigDecimal::zero(), exponent: 0, ..self }
+        }
+        if z > 0 {
+            m.drain(..z);
+            e += z as i64;
+        }
+        Self { negative, mantissa: BigDecimal { digits: m }, exponent: e, ..self }
+    }
+    // Округление неточного результата: если sticky, то истинное значение
+    // чуть больше m * 10^e по модулю (m должно содержать не менее precision + 2 цифр)
+    fn round_sticky(&self, negative: bool, m: Vec<u8>, e: i64, sticky: bool) -> Self {
+        if sticky {
+            let mut m = shl10(&m, 1);
+            m[0] = 1;
+            self.clone().with_parts(negative, m, e - 1)
+        } else {
+            self.clone().with_parts(negative, m, e)
+        }
+    }
+    // Точность и округление результата операции
+    fn context(&self, rhs: &Self) -> Self {
+        Self::zero(self.precision.max(rhs.precision)).with_rounding(self.rounding)
+    }
+}
+
+// Сравнение модулей
+fn cmp_abs(x: &BigFloat, y: &BigFloat) -> Ordering {
+    if x.is_zero() || y.is_zero() {
+        return x.is_zero().cmp(&y.is_zero()).reverse()
+    }
+    x.adjusted().cmp(&y.adjusted()).then_with(|| {
+        // выравниваем мантиссы по старшей цифре
+        let (a, b) = (&x.mantissa.digits, &y.mantissa.digits);
+        if a.len() >= b.len() {
+            cmp_vec(a, &shl10(b, a.len() - b.len()))
+        } else {
+            cmp_vec(&shl10(a, b.len() - a.len()), b)
+        }
+    })
+}
+
+// x + y (с учетом знаков)
+fn add_float(x: &BigFloat, y: &BigFloat, y_negative: bool) -> BigFloat {
+    let ctx = x.context(y);
+    if y.is_zero() { return ctx.with_parts(x.negative, x.mantissa.digits.clone(), x.exponent) }
+    if x.is_zero() { return ctx.with_parts(y_negative, y.mantissa.digits.clone(), y.exponent) }
+    // x - большее по модулю
+    let (x, y, x_neg, y_neg) = if cmp_abs(x, y) == Ordering::Less {
+        (y, x, y_negative, x.negative)
+    } else {
+        (x, y, x.negative, y_negative)
+    };
+    // если y лежит ниже разряда округления, заменяем его одной "липкой" цифрой:
+    // на результат округления он влияет только знаком
+    let low = x.adjusted() - ctx.precision as i64 - 2;
+    let (ym, ye) = if y.adjusted() < low {
+        (vec![1u8], low - 1)
+    } else {
+        (y.mantissa.digits.clone(), y.exponent)
+    };
+    let e = x.exponent.min(ye);
+    let a = shl10(&x.mantissa.digits, (x.exponent - e) as usize);
+    let b = shl10(&ym, (ye - e) as usize);
+    if x_neg == y_neg {
+        ctx.with_parts(x_neg, add_vec(&a, &b), e)
+    } else {
+        match cmp_vec(&a, &b) {
+            Ordering::Less => ctx.with_parts(y_neg, sub_vec(&b, &a), e),
+            _ => ctx.with_parts(x_neg, sub_vec(&a, &b), e)
+        }
+    }
+}
+
+// x * y
+fn mul_float(x: &BigFloat, y: &BigFloat) -> BigFloat {
+    x.context(y).with_parts(
+        x.negative != y.negative,
+        mul_vec(&x.mantissa.digits, &y.mantissa.digits),
+        x.exponent + y.exponent
+    )
+}
+
+// x / y
+fn div_float(x: &BigFloat, y: &BigFloat) -> BigFloat {
+    if y.is_zero() { panic!("division by zero"); }
+    let ctx = x.context(y);
+    if x.is_zero() { return ctx }
+    // частное мантисс должно содержать не менее precision + 2 цифр
+    let (a, b) = (&x.mantissa.digits, &y.mantissa.digits);
+    let s = (ctx.precision + 2 + b.len()).saturating_sub(a.len());
+    let (q, r) = divmod_vec(&shl10(a, s), b);
+    let sticky = r != [0u8];
+    ctx.round_sticky(x.negative != y.negative, q, x.exponent - y.exponent - s as i64, sticky)
+}
+
+// Разбор строки "[+-]<цифры>[.<цифры>][e[+-]<цифры>]"
+fn parse_float(s: &str) -> (bool, Vec<u8>, i64) {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s))
+    };
+    let (body, exp) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], s[i + 1..].parse::<i64>().expect("Incorrect exponent")),
+        None => (s, 0)
+    };
+    let (int, frac) = body.split_once('.').unwrap_or((body, ""));
+    if int.is_empty() && frac.is_empty() { panic!("Incorrect number: {s}"); }
+    let m: Vec<u8> = int.bytes().chain(frac.bytes()).rev()
+        .map(|x| if x.is_ascii_digit() { x - b'0' } else { panic!("Incorrect digit: {}", x as char) })
+        .collect();
+    (negative, m, exp - frac.len() as i64)
+}
+
+impl From<&str> for BigFloat {
+    fn from(value: &str) -> Self {
+        let (negative, m, e) = parse_float(value);
+        Self::zero(DEFAULT_PRECISION.max(m.len())).with_parts(negative, m, e)
+    }
+}
+
+impl From<BigDecimal> for BigFloat {
+    fn from(value: BigDecimal) -> Self {
+        let p = DEFAULT_PRECISION.max(value.digits.len());
+        Self::from_big(value, p)
+    }
+}
+
+// Отображение: обычная запись для умеренных порядков, иначе научная
+impl Display for BigFloat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.negative { write!(f, "-")?; }
+        let digits: String = self.mantissa.digits.iter().rev().map(|d| (b'0' + d) as char).collect();
+        let adj = self.adjusted();
+        if self.exponent >= 0 && adj < self.precision as i64 {
+            write!(f, "{digits}{}", "0".repeat(self.exponent as usize))
+        } else if self.exponent < 0 && adj >= -6 {
+            if adj >= 0 {
+                let (int, frac) = digits.split_at(adj as usize + 1);
+                write!(f, "{int}.{frac}")
+            } else {
+                write!(f, "0.{}{digits}", "0".repeat((-adj - 1) as usize))
+            }
+        } else {
+            let (int, frac) = digits.split_at(1);
+            if frac.is_empty() {
+                write!(f, "{int}e{adj:+}")
+            } else {
+                write!(f, "{int}.{frac}e{adj:+}")
+            }
+        }
+    }
+}
+
+impl Debug for BigFloat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BigFloat {}
+
+impl PartialOrd for BigFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigFloat {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => cmp_abs(self, other),
+            (true, true) => cmp_abs(other, self)
+        }
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Арифметика
+impl Neg for BigFloat {
+    type Output = BigFloat;
+    fn neg(self) -> Self::Output {
+        let negative = !self.negative && !self.is_zero();
+        Self { negative, ..self }
+    }
+}
+
+// a + b
+impl Add for BigFloat {
+    type Output = BigFloat;
+    fn add(self, rhs: Self) -> Self::Output {
+        add_float(&self, &rhs, rhs.negative)
+    }
+}
+
+impl AddAssign for BigFloat {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = add_float(self, &rhs, rhs.negative);
+    }
+}
+
+// a - b
+impl Sub for BigFloat {
+    type Output = BigFloat;
+    fn sub(self, rhs: Self) -> Self::Output {
+        add_float(&self, &rhs, !rhs.negative)
+    }
+}
+
+impl SubAssign for BigFloat {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = add_float(self, &rhs, !rhs.negative);
+    }
+}
+
+// a * b
+impl Mul for BigFloat {
+    type Output = BigFloat;
+    fn mul(self, rhs: Self) -> Self::Output {
+        mul_float(&self, &rhs)
+    }
+}
+
+impl MulAssign for BigFloat {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = mul_float(self, &rhs);
+    }
+}
+
+// a / b
+impl Div for BigFloat {
+    type Output = BigFloat;
+    fn div(self, rhs: Self) -> Self::Output {
+        div_float(&self, &rhs)
+    }
+}
+
+impl DivAssign for BigFloat {
+    fn div_assign(&mut self, rhs: Self) {
+        *self = div_float(self, &rhs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::oper::{from_u128, to_u128};
+    use super::*;
+
+    const MODES: [RoundingMode; 6] = [
+        RoundingMode::HalfEven, RoundingMode::HalfUp, RoundingMode::Down,
+        RoundingMode::Up, RoundingMode::Ceiling, RoundingMode::Floor
+    ];
+
+    // Округление v / 10^k в целых числах
+    fn round_u128(v: u128, k: u32, negative: bool, mode: RoundingMode) -> u128 {
+        let p = 10u128.pow(k);
+        let (q, r) = (v / p, v % p);
+        let up = match mode {
+            RoundingMode::HalfEven => 2 * r > p || (2 * r == p && q % 2 == 1),
+            RoundingMode::HalfUp => 2 * r >= p,
+            RoundingMode::Down => false,
+            RoundingMode::Up => r > 0,
+            RoundingMode::Ceiling => r > 0 && !negative,
+            RoundingMode::Floor => r > 0 && negative
+        };
+        q + up as u128
+    }
+
+    #[test]
+    fn round_digits_at_ties() {
+        // 2.5, 3.5, 9.5, 2.51 и 2.49 со знаками, в порядке MODES
+        let cases: [(u128, bool, [u128; 6]); 10] = [
+            (25, false, [2, 3, 2, 3, 3, 2]),
+            (25, true, [2, 3, 2, 3, 2, 3]),
+            (35, false, [4, 4, 3, 4, 4, 3]),
+            (35, true, [4, 4, 3, 4, 3, 4]),
+            (95, false, [10, 10, 9, 10, 10, 9]),
+            (95, true, [10, 10, 9, 10, 9, 10]),
+            (251, false, [3, 3, 2, 3, 3, 2]),
+            (251, true, [3, 3, 2, 3, 2, 3]),
+            (249, false, [2, 2, 2, 3, 3, 2]),
+            (249, true, [2, 2, 2, 3, 2, 3])
+        ];
+        for (v, negative, expected) in cases {
+            let k = if v >= 100 { 2 } else { 1 };
+            for (mode, e) in MODES.into_iter().zip(expected) {
+                let res = round_digits(&from_u128(v), k, negative, mode);
+                assert_eq!(to_u128(&res), e, "{v} / 10^{k}, negative = {negative}, {mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn round_digits_matches_u128() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20000 {
+            let v = rng.gen_range(0..10u128.pow(30)) >> rng.gen_range(0..100);
+            // в том числе отбрасывание всех цифр и больше, чем есть
+            let k = rng.gen_range(0..34);
+            let negative = rng.gen();
+            for mode in MODES {
+                let res = round_digits(&from_u128(v), k as usize, negative, mode);
+                assert_eq!(to_u128(&res), round_u128(v, k, negative, mode), "{v} / 10^{k}, {mode:?}");
+            }
+        }
+    }
+
+    // x op y с точностью precision в каждом из режимов MODES
+    fn check_modes(op: fn(BigFloat, BigFloat) -> BigFloat, x: &str, y: &str, precision: usize, expected: [&str; 6]) {
+        for (mode, e) in MODES.into_iter().zip(expected) {
+            let a = BigFloat::parse(x, precision).with_rounding(mode);
+            let b = BigFloat::parse(y, precision).with_rounding(mode);
+            assert_eq!(op(a, b), BigFloat::parse(e, precision), "{x}, {y}, {mode:?}");
+        }
+    }
+
+    #[test]
+    fn add_rounding() {
+        // точная половина и чуть больше половины
+        check_modes(|a, b| a + b, "12344", "0.5", 5, ["12344", "12345", "12344", "12345", "12345", "12344"]);
+        check_modes(|a, b| a + b, "12344", "0.50001", 5, ["12345", "12345", "12344", "12345", "12345", "12344"]);
+        // слагаемое далеко за разрядом округления заменяется липкой цифрой
+        check_modes(|a, b| a + b, "12344", "1e-10", 5, ["12344", "12344", "12344", "12345", "12345", "12344"]);
+        check_modes(|a, b| a + b, "12345", "-1e-10", 5, ["12345", "12345", "12344", "12345", "12345", "12344"]);
+        check_modes(|a, b| a + b, "100000", "-1e-10", 5, ["100000", "100000", "99999", "100000", "100000", "99999"]);
+        check_modes(|a, b| a + b, "-12344", "-1e-10", 5, ["-12344", "-12344", "-12344", "-12345", "-12344", "-12345"]);
+        check_modes(|a, b| a - b, "1e-10", "12344", 5, ["-12344", "-12344", "-12343", "-12344", "-12343", "-12344"]);
+    }
+
+    #[test]
+    fn div_rounding() {
+        check_modes(|a, b| a / b, "1", "3", 5, ["0.33333", "0.33333", "0.33333", "0.33334", "0.33334", "0.33333"]);
+        check_modes(|a, b| a / b, "-2", "3", 5, ["-0.66667", "-0.66667", "-0.66666", "-0.66667", "-0.66666", "-0.66667"]);
+        // точная половина
+        check_modes(|a, b| a / b, "1", "8", 2, ["0.12", "0.13", "0.12", "0.13", "0.13", "0.12"]);
+        // 6 / 71 = 0.0845070...: в вычисленных цифрах частного 8450 - половина,
+        // вверх округляет только ненулевой остаток
+        check_modes(|a, b| a / b, "6", "71", 2, ["0.085", "0.085", "0.084", "0.085", "0.085", "0.084"]);
+    }
+
+    #[test]
+    fn sqrt_rounding() {
+        // sqrt(418) = 20.4450483..., sqrt(3) = 1.73205080...: ненулевой остаток за половиной,
+        // точной половины у корня из числа с precision цифрами не бывает
+        let cases = [
+            ("418", 4, ["20.45", "20.45", "20.44", "20.45", "20.45", "20.44"]),
+            ("3", 5, ["1.7321", "1.7321", "1.7320", "1.7321", "1.7321", "1.7320"]),
+            ("2", 3, ["1.41", "1.41", "1.41", "1.42", "1.42", "1.41"]),
+            ("1.44", 3, ["1.2", "1.2", "1.2", "1.2", "1.2", "1.2"])
+        ];
+        for (x, precision, expected) in cases {
+            for (mode, e) in MODES.into_iter().zip(expected) {
+                let r = BigFloat::parse(x, precision).with_rounding(mode).sqrt();
+                assert_eq!(r, BigFloat::parse(e, precision), "sqrt({x}), {mode:?}");
+            }
+        }
+    }
+
+    // Проверка относительной погрешности по f64
+    fn check_f64(x: &BigFloat, expected: f64) {
+        let rel = (x.to_f64() - expected).abs() / expected.abs();
+        assert!(rel < 1e-15, "{x} vs {expected:e}");
+    }
+
+    #[test]
+    fn ops_match_f64() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..2000 {
+            let a: f64 = rng.gen_range(0.5..2.0) * 10f64.powi(rng.gen_range(-30..30));
+            let b: f64 = rng.gen_range(0.5..2.0) * 10f64.powi(rng.gen_range(-30..30));
+            // десятичная запись, по которой f64 восстанавливается без потерь
+            let x = BigFloat::parse(&format!("{a:e}"), 30);
+            let y = BigFloat::parse(&format!("{b:e}"), 30);
+            check_f64(&(x.clone() + y.clone()), a + b);
+            check_f64(&(x.clone() * y.clone()), a * b);
+            check_f64(&(x.clone() / y.clone()), a / b);
+            check_f64(&x.sqrt(), a.sqrt());
+        }
+    }
+}
@@ -89,9 +89,7 @@ pub fn fft(input_arr: &mut [Complex], rev_indexes: &[usize], rev: bool) {
 // можно вычислить и закэшировать все перестановки
 pub fn rev_swap(n: usize) -> Vec<usize> {
     // println!("rev_swap: {n}");
-    let mut v = Vec::with_capacity(n);
-    unsafe { v.set_len(n) }
-    v.fill(0);
+    let mut v = vec![0; n];
     let mut j = 0;
     for ind in 1..n {
         let mut bit = n >> 1;
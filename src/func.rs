@@ -0,0 +1,475 @@
+use std::cmp::Ordering;
+use crate::big::BigDecimal;
+use crate::consts;
+use crate::float::{round_digits, BigFloat, RoundingMode};
+use crate::oper::{add_vec, cmp_vec, from_u128, mul_vec, shl10, sub_vec, trim_vec};
+
+// Запас точности для промежуточных вычислений
+pub const GUARD: usize = 10;
+
+// Размер отрезка, начиная с которого ветви двоичного разбиения считаются параллельно
+const PAR_LIMIT: u64 = 256;
+
+//-------------------------------------------------------------------------------------------------
+// Точные целые со знаком: (-1)^negative * m * 10^e
+// нули младших разрядов храним в показателе, так множители вида q * 10^k дешевле
+#[derive(Clone)]
+pub struct Exact {
+    negative: bool,
+    m: Vec<u8>,
+    e: usize
+}
+
+impl Exact {
+    pub fn new(negative: bool, mut m: Vec<u8>, mut e: usize) -> Self {
+        trim_vec(&mut m);
+        if m == [0u8] { return Self { negative: false, m, e: 0 } }
+        let z = m.iter().take_while(|&&x| x == 0).count();
+        if z > 0 {
+            m.drain(..z);
+            e += z;
+        }
+        Self { negative, m, e }
+    }
+    pub fn int(x: i128) -> Self {
+        Self::new(x < 0, from_u128(x.unsigned_abs()), 0)
+    }
+    pub fn one() -> Self { Self::int(1) }
+    pub fn mul(&self, rhs: &Self) -> Self {
+        Self::new(self.negative != rhs.negative, mul_vec(&self.m, &rhs.m), self.e + rhs.e)
+    }
+    pub fn add(&self, rhs: &Self) -> Self {
+        let e = self.e.min(rhs.e);
+        let a = shl10(&self.m, self.e - e);
+        let b = shl10(&rhs.m, rhs.e - e);
+        if self.negative == rhs.negative {
+            return Self::new(self.negative, add_vec(&a, &b), e)
+        }
+        match cmp_vec(&a, &b) {
+            Ordering::Less => Self::new(rhs.negative, sub_vec(&b, &a), e),
+            _ => Self::new(self.negative, sub_vec(&a, &b), e)
+        }
+    }
+    pub fn to_float(&self, precision: usize) -> BigFloat {
+        BigFloat::from_parts(self.negative, BigDecimal { digits: self.m.clone() }, self.e as i64, precision)
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Двоичное разбиение ряда
+// S = sum_{n=0}^{N-1} a(n) / b(n) * prod_{i=0}^{n} p(i) / q(i)
+// term(n) возвращает [a(n), b(n), p(n), q(n)]
+struct Split {
+    p: Exact,
+    q: Exact,
+    b: Exact,
+    t: Exact
+}
+
+fn split<F>(lo: u64, hi: u64, term: &F) -> Split
+where
+    F: Fn(u64) -> [Exact; 4] + Sync
+{
+    if hi - lo == 1 {
+        let [a, b, p, q] = term(lo);
+        let t = a.mul(&p);
+        return Split { p, q, b, t }
+    }
+    let mid = (lo + hi) / 2;
+    let (l, r) = if hi - lo >= PAR_LIMIT {
+        rayon::join(|| split(lo, mid, term), || split(mid, hi, term))
+    } else {
+        (split(lo, mid, term), split(mid, hi, term))
+    };
+    // T = Br * Qr * Tl + Bl * Pl * Tr
+    let t = r.b.mul(&r.q).mul(&l.t).add(&l.b.mul(&l.p).mul(&r.t));
+    Split { p: l.p.mul(&r.p), q: l.q.mul(&r.q), b: l.b.mul(&r.b), t }
+}
+
+// Сумма первых n членов ряда
+pub fn sum_series<F>(n: u64, precision: usize, term: &F) -> BigFloat
+where
+    F: Fn(u64) -> [Exact; 4] + Sync
+{
+    let s = split(0, n, term);
+    s.t.to_float(precision) / s.b.mul(&s.q).to_float(precision)
+}
+
+// Число членов ряда, после которого они меньше 10^-(precision + 2)
+// log_ratio(n) - десятичный логарифм отношения n-го члена к (n-1)-му
+fn terms<F: Fn(u64) -> f64>(precision: usize, log_ratio: F) -> u64 {
+    let mut s = 0f64;
+    let mut n = 0u64;
+    while s > -(precision as f64 + 2.) || log_ratio(n + 1) > -0.3 {
+        n += 1;
+        s += log_ratio(n);
+    }
+    n + 1
+}
+
+//-------------------------------------------------------------------------------------------------
+// Ряды от рациональных аргументов x = num / 10^k
+
+// exp(x)
+fn exp_series(num: &Exact, k: usize, lx: f64, precision: usize) -> BigFloat {
+    let den = Exact::new(false, vec![1], k);
+    let n = terms(precision, |i| lx - (i as f64).log10());
+    sum_series(n, precision, &|i| {
+        if i == 0 { return [Exact::one(), Exact::one(), Exact::one(), Exact::one()] }
+        [Exact::one(), Exact::one(), num.clone(), den.mul(&Exact::int(i as i128))]
+    })
+}
+
+// (sin(x), cos(x))
+fn sin_cos_series(num: &Exact, k: usize, lx: f64, precision: usize) -> (BigFloat, BigFloat) {
+    let den = Exact::new(false, vec![1], 2 * k);
+    let x2 = num.mul(num);
+    let minus_x2 = Exact { negative: true, ..x2 };
+    let n = terms(precision, |i| 2. * lx - ((2 * i) as f64 * (2 * i + 1) as f64).log10());
+    let (s, c) = rayon::join(
+        || sum_series(n, precision, &|i| {
+            if i == 0 { return [Exact::one(), Exact::one(), Exact::one(), Exact::one()] }
+            let f = (2 * i) as i128 * (2 * i + 1) as i128;
+            [Exact::one(), Exact::one(), minus_x2.clone(), den.mul(&Exact::int(f))]
+        }),
+        || sum_series(n, precision, &|i| {
+            if i == 0 { return [Exact::one(), Exact::one(), Exact::one(), Exact::one()] }
+            let f = (2 * i - 1) as i128 * (2 * i) as i128;
+            [Exact::one(), Exact::one(), minus_x2.clone(), den.mul(&Exact::int(f))]
+        })
+    );
+    // sin(x) = x * (1 - x^2 / 3! + ...)
+    let x = BigFloat::from_parts(num.negative, BigDecimal { digits: num.m.clone() }, num.e as i64 - k as i64, precision);
+    (s * x, c)
+}
+
+// atan(1/k) при alternate и atanh(1/k) без него
+pub fn arc_inv_series(k: u64, alternate: bool, precision: usize) -> BigFloat {
+    let k2 = Exact::int(k as i128 * k as i128);
+    let p = Exact::int(if alternate { -1 } else { 1 });
+    let n = terms(precision, |_| -2. * (k as f64).log10());
+    let s = sum_series(n, precision, &|i| {
+        if i == 0 { return [Exact::one(), Exact::one(), Exact::one(), Exact::one()] }
+        [Exact::one(), Exact::int(2 * i as i128 + 1), p.clone(), k2.clone()]
+    });
+    s / BigFloat::from_big(BigDecimal { digits: from_u128(k as u128) }, precision)
+}
+
+pub fn int(x: i64, precision: usize) -> BigFloat {
+    let v = BigFloat::from_big(BigDecimal { digits: from_u128(x.unsigned_abs() as u128) }, precision);
+    if x < 0 { -v } else { v }
+}
+
+// Разбиение аргумента на части x = sum x_j, x_j = num_j / 10^k_j,
+// где k_j удваивается, а в num_j около k_j / 2 цифр (bit-burst)
+// возвращает (num_j, k_j, log10 |x_j|)
+fn bursts(x: &BigFloat, precision: usize) -> Vec<(Exact, usize, f64)> {
+    // фиксированная точка: X = x * 10^f, в X не менее precision значащих цифр
+    let f = (precision as i64 - x.adjusted().min(0)) as usize;
+    let shift = x.exponent() + f as i64;
+    let digits = if shift >= 0 {
+        shl10(&x.mantissa().digits, shift as usize)
+    } else {
+        round_digits(&x.mantissa().digits, (-shift) as usize, x.is_negative(), RoundingMode::HalfEven)
+    };
+    let mut res = Vec::new();
+    let (mut lo, mut hi) = (0usize, 1usize);
+    loop {
+        // цифры на позициях 10^-(lo + 1) .. 10^-hi, для первой части - и целая часть
+        let hi_c = hi.min(f);
+        let from = f - hi_c;
+        let to = if lo == 0 { digits.len() } else { (f - lo).min(digits.len()) };
+        if from < to {
+            let part = Exact::new(x.is_negative(), digits[from..to].to_vec(), 0);
+            if part.m != [0u8] {
+                let lx = (part.m.len() + part.e) as f64 - hi_c as f64;
+                res.push((part, hi_c, lx));
+            }
+        }
+        if hi >= f { break }
+        (lo, hi) = (hi, hi * 2);
+    }
+    res
+}
+
+//-------------------------------------------------------------------------------------------------
+// Элементарные функции
+impl BigFloat {
+    // e^x
+    pub fn exp(&self) -> Self {
+        let p = self.precision();
+        if self.is_zero() { return BigFloat::one(p).with_rounding(self.rounding()) }
+        if self.adjusted() >= 18 {
+            if self.is_negative() { return BigFloat::zero(p).with_rounding(self.rounding()) }
+            panic!("exp overflow");
+        }
+        let wp = p + GUARD;
+        if self.adjusted() < 1 {
+            return exp_small(self, wp).with_precision(p).with_rounding(self.rounding())
+        }
+        // x = n * ln(10) + r, |r| <= ln(10) / 2, e^x = 10^n * e^r
+        let extra = self.adjusted().max(0) as usize;
+        let l10 = consts::ln10(wp + extra);
+        let x = self.clone().with_precision(wp + extra);
+        let n = nearest_int(&(x.clone() / l10.clone()));
+        let r = (x - l10 * n.clone()).with_precision(wp);
+        let y = exp_small(&r, wp);
+        let n = n.trunc().digits.iter().rev().fold(0i64, |acc, &d| acc * 10 + d as i64);
+        let n = if self.is_negative() { -n } else { n };
+        BigFloat::from_parts(false, y.mantissa().clone(), y.exponent() + n, p)
+            .with_rounding(self.rounding())
+    }
+    // натуральный логарифм
+    pub fn ln(&self) -> Self {
+        if self.is_negative() || self.is_zero() { panic!("logarithm of a non-positive number"); }
+        let p = self.precision();
+        // x = y * 10^a, y в [0.316, 3.16)
+        let mut a = self.adjusted();
+        let mut y = BigFloat::from_parts(false, self.mantissa().clone(), self.exponent() - a, p + 1);
+        if y.to_f64() > 10f64.sqrt() {
+            a += 1;
+            y = BigFloat::from_parts(false, self.mantissa().clone(), self.exponent() - a, p + 1);
+        }
+        // при y около 1 нужен запас на потерю значащих цифр
+        let d = y.clone() - BigFloat::one(p + 1);
+        if d.is_zero() && a == 0 { return BigFloat::zero(p).with_rounding(self.rounding()) }
+        let loss = if d.is_zero() { 0 } else { (-d.adjusted()).max(0) as usize };
+        let wp = p + GUARD + loss;
+        // Ньютон для e^z = y: z = z + y * e^(-z) - 1
+        let y = y.with_precision(wp);
+        let mut z = BigFloat::parse(&format!("{:e}", y.to_f64().ln()), wp);
+        for q in precision_steps(wp) {
+            let z_q = z.clone().with_precision(q);
+            let e = exp_small(&-z_q.clone(), q);
+            z = z_q + y.clone().with_precision(q) * e - BigFloat::one(q);
+        }
+        let res = if a == 0 { z } else {
+            let extra = (a.unsigned_abs() as f64).log10() as usize + 1;
+            z + consts::ln10(wp + extra) * int(a, wp + extra)
+        };
+        res.with_precision(p).with_rounding(self.rounding())
+    }
+    // синус
+    pub fn sin(&self) -> Self {
+        let (s, _) = sin_cos(self, self.precision() + GUARD);
+        s.with_precision(self.precision()).with_rounding(self.rounding())
+    }
+    // косинус
+    pub fn cos(&self) -> Self {
+        let (_, c) = sin_cos(self, self.precision() + GUARD);
+        c.with_precision(self.precision()).with_rounding(self.rounding())
+    }
+    // тангенс
+    pub fn tan(&self) -> Self {
+        let (s, c) = sin_cos(self, self.precision() + GUARD);
+        (s / c).with_precision(self.precision()).with_rounding(self.rounding())
+    }
+    // арктангенс
+    pub fn atan(&self) -> Self {
+        let p = self.precision();
+        if self.is_zero() { return self.clone() }
+        let wp = p + GUARD;
+        let x = self.abs().with_precision(wp);
+        // atan(x) = pi/2 - atan(1/x) при x > 1
+        let inv = x > BigFloat::one(wp);
+        let x = if inv { BigFloat::one(wp) / x } else { x };
+        // Ньютон для tan(y) = x: y = y - (sin y - x cos y) / (cos y + x sin y)
+        let mut y = BigFloat::parse(&format!("{:e}", x.to_f64().atan()), wp);
+        if y.is_zero() { y = x.clone() }
+        for q in precision_steps(wp) {
+            let y_q = y.with_precision(q);
+            let x_q = x.clone().with_precision(q);
+            let (s, c) = sin_cos_reduced(&y_q, q);
+            let num = s.clone() - x_q.clone() * c.clone();
+            let den = c + x_q * s;
+            y = y_q - num / den;
+        }
+        if inv {
+            y = consts::pi(wp) / int(2, wp) - y;
+        }
+        let y = if self.is_negative() { -y } else { y };
+        y.with_precision(p).with_rounding(self.rounding())
+    }
+    // x^y
+    pub fn pow(&self, y: &BigFloat) -> Self {
+        let p = self.precision();
+        if y.is_zero() { return BigFloat::one(p).with_rounding(self.rounding()) }
+        // целый показатель - возведение в степень умножениями
+        if y.exponent() >= 0 && y.adjusted() < 18 {
+            let n = y.trunc().digits.iter().rev().fold(0i64, |acc, &d| acc * 10 + d as i64);
+            return self.powi(if y.is_negative() { -n } else { n })
+        }
+        if self.is_zero() {
+            if y.is_negative() { panic!("zero to a negative power"); }
+            return self.clone()
+        }
+        if self.is_negative() { panic!("negative number to a fractional power"); }
+        // x^y = e^(y * ln x), абсолютная ошибка y * ln x переходит в относительную ошибку e^...
+        let ln_digits = ((self.adjusted().unsigned_abs() + 1) as f64).log10() as usize + 1;
+        let extra = (y.adjusted() + 1).max(0) as usize + ln_digits;
+        let wp = p + GUARD + extra;
+        let l = self.clone().with_precision(wp).ln();
+        (y.clone().with_precision(wp) * l).exp().with_precision(p).with_rounding(self.rounding())
+    }
+    // x^n
+    pub fn powi(&self, n: i64) -> Self {
+        let p = self.precision();
+        if n == 0 { return BigFloat::one(p).with_rounding(self.rounding()) }
+        let wp = p + GUARD + 20;
+        let mut base = self.clone().with_precision(wp);
+        let mut res = BigFloat::one(wp);
+        let mut m = n.unsigned_abs();
+        while m > 0 {
+            if m & 1 == 1 { res *= base.clone(); }
+            m >>= 1;
+            if m > 0 { base = base.clone() * base; }
+        }
+        if n < 0 { res = BigFloat::one(wp) / res; }
+        res.with_precision(p).with_rounding(self.rounding())
+    }
+}
+
+// Ближайшее к x целое (половины округляются от нуля)
+fn nearest_int(x: &BigFloat) -> BigFloat {
+    let q = x.precision();
+    let half = BigFloat::parse("0.5", q);
+    let n = BigFloat::from_big((x.abs() + half).trunc(), q);
+    if x.is_negative() { -n } else { n }
+}
+
+// Последовательность точностей для итераций Ньютона: ..., wp/4, wp/2, wp
+fn precision_steps(wp: usize) -> Vec<usize> {
+    let mut res = vec![wp];
+    let mut q = wp;
+    while q > 30 {
+        q = q / 2 + 2;
+        res.push(q);
+    }
+    res.push(q);
+    res.reverse();
+    res
+}
+
+// e^x без приведения аргумента (для небольших |x|)
+// при x < 0 считаем 1 / e^|x|, чтобы не терять цифры в знакопеременном ряде
+fn exp_small(x: &BigFloat, wp: usize) -> BigFloat {
+    let y = bursts(&x.abs(), wp).iter()
+        .map(|(num, k, lx)| exp_series(num, *k, *lx, wp))
+        .fold(BigFloat::one(wp), |acc, y| acc * y);
+    if x.is_negative() { BigFloat::one(wp) / y } else { y }
+}
+
+// (sin(x), cos(x)) для небольших x
+fn sin_cos_reduced(x: &BigFloat, wp: usize) -> (BigFloat, BigFloat) {
+    // sin(a + b) = sin a cos b + cos a sin b, cos(a + b) = cos a cos b - sin a sin b
+    bursts(x, wp).iter()
+        .map(|(num, k, lx)| sin_cos_series(num, *k, *lx, wp))
+        .fold((BigFloat::zero(wp), BigFloat::one(wp)), |(s1, c1), (s2, c2)| {
+            (s1.clone() * c2.clone() + c1.clone() * s2.clone(), c1 * c2 - s1 * s2)
+        })
+}
+
+// (sin(x), cos(x)) с приведением аргумента: x = k * pi/2 + r, |r| <= pi/4
+fn sin_cos(x: &BigFloat, wp: usize) -> (BigFloat, BigFloat) {
+    if x.is_zero() { return (BigFloat::zero(wp), BigFloat::one(wp)) }
+    // при |x| < 2 приведение не нужно, но около pi/2 теряются значащие цифры косинуса
+    if x.adjusted() < 1 && x.abs().to_f64() < 2. {
+        let (s, c) = sin_cos_reduced(&x.clone().with_precision(wp), wp);
+        let lost = (-c.adjusted()).max(0) as usize;
+        if lost + 2 <= GUARD { return (s, c) }
+        let q = wp + lost;
+        return sin_cos_reduced(&x.clone().with_precision(q), q)
+    }
+    // при x около k * pi/2 теряются значащие цифры r - повторяем с большей точностью
+    let mut extra = x.adjusted().max(0) as usize + GUARD;
+    loop {
+        let q = wp + extra;
+        let half_pi = consts::pi(q) / int(2, q);
+        let xq = x.clone().with_precision(q);
+        let k = nearest_int(&(xq.clone() / half_pi.clone()));
+        let r = xq - half_pi * k.clone();
+        let lost = if r.is_zero() { extra } else { (-r.adjusted()).max(0) as usize };
+        if lost + GUARD > extra {
+            extra = lost + 2 * GUARD;
+            continue
+        }
+        let (s, c) = sin_cos_reduced(&r.with_precision(wp), wp);
+        // четверть определяется двумя младшими цифрами k
+        let d = &k.trunc().digits;
+        let m = (d[0] + 10 * d.get(1).copied().unwrap_or(0)) % 4;
+        let m = if k.is_negative() { (4 - m) % 4 } else { m };
+        return match m {
+            0 => (s, c),
+            1 => (c, -s),
+            2 => (-s, -c),
+            _ => (-c, s)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Точности, на которых проверяются функции
+    const PRECISIONS: [usize; 3] = [10, 40, 120];
+
+    // Результат отличается от эталона не больше чем на единицу последнего разряда
+    fn check(x: &BigFloat, expected: &str) {
+        let p = x.precision();
+        let e = BigFloat::parse(expected, p + 5);
+        let ulp = BigFloat::parse(&format!("1e{}", e.adjusted() - p as i64 + 1), p);
+        assert!((x.clone() - e.clone()).abs() <= ulp, "{x} vs {e}");
+    }
+
+    // f(x) на всех точностях PRECISIONS
+    fn check_fn(f: fn(&BigFloat) -> BigFloat, x: &str, expected: &str) {
+        for p in PRECISIONS {
+            check(&f(&BigFloat::parse(x, p)), expected);
+        }
+    }
+
+    #[test]
+    fn exp() {
+        check_fn(BigFloat::exp, "1", "2.718281828459045235360287471352662497757247093699959574966967627724076630353547594571382178525166427427466391932003059921817413597");
+        check_fn(BigFloat::exp, "-2.5", "0.08208499862389879516952867446715980783780412101543664884575841051522475688041097130975157152123646590871771482383259579320841208263");
+        check_fn(BigFloat::exp, "100", "26881171418161354484126255515800135873611118.77374192241519160861528028703490956491415887109721984571081167087919057606869759770976");
+        check_fn(BigFloat::exp, "0.001", "1.001000500166708341668055753993058311563076200580701460228514674460359748251448298412718226004153260943068218872095099342063678696");
+        assert_eq!(BigFloat::zero(10).exp(), BigFloat::one(10));
+    }
+
+    #[test]
+    fn ln() {
+        check_fn(BigFloat::ln, "2", "0.6931471805599453094172321214581765680755001343602552541206800094933936219696947156058633269964186875420014810205706857336855202358");
+        check_fn(BigFloat::ln, "0.001", "-6.907755278982137052053974364053092622803304465886318928099983702902717829032057440707991615268794895025903352126858745900228576395");
+        // потеря значащих цифр около 1
+        check_fn(BigFloat::ln, "1.0000001", "0.00000009999999500000033333330833333533333316666668095237970238106349205349206440115431782107551337479908986575645950646634470099502785323");
+        check_fn(BigFloat::ln, "123456.789", "11.72364648718588098113995898391011158691037737513408304708510624218949963822429433694812480492150780045257317663842557452005537164");
+        assert!(BigFloat::one(10).ln().is_zero());
+    }
+
+    #[test]
+    fn trigonometry() {
+        check_fn(BigFloat::sin, "1", "0.8414709848078965066525023216302989996225630607983710656727517099919104043912396689486397435430526958543490379079206742932591189210");
+        // приведение аргумента по модулю pi
+        check_fn(BigFloat::sin, "100", "-0.5063656411097587936565576104597854320650327212906573234433924735943579134194766964992366645129273922072440893925638404173419525871");
+        check_fn(BigFloat::cos, "100", "0.8623188722876839341019385139508425355100840085355108292801621126927210880509266241030951056842772850671356075551623304811055280680");
+        check_fn(BigFloat::cos, "-0.5", "0.8775825618903727161162815826038296519916451971097440529976108683159507632742139474057941840846822583554784005931090539934138279768");
+        check_fn(BigFloat::tan, "0.5", "0.5463024898437905132551794657802853832975517201797912461640913859329075105180258157151806482706562185891048626002641142654932300912");
+        check_fn(BigFloat::atan, "1", "0.7853981633974483096156608458198757210492923498437764552437361480769541015715522496570087063355292669955370216283205766617734611524");
+        check_fn(BigFloat::atan, "-3", "-1.249045772398254425829917077281090123077829404129896719054669236797151965737293954957608990320417159552066873879511414175279279334");
+        check_fn(BigFloat::atan, "0.1", "0.09966865249116202737844611987802059024327832250431464801550877681002774744755065442061262443428637157955838640882739896956792706656");
+    }
+
+    #[test]
+    fn pow() {
+        for p in PRECISIONS {
+            let two = BigFloat::parse("2", p);
+            check(&two.pow(&BigFloat::parse("0.5", p)), "1.414213562373095048801688724209698078569671875376948073176679737990732478462107038850387534327641572735013846230912297024924836056");
+            let ten = BigFloat::parse("10", p);
+            check(&ten.pow(&BigFloat::parse("-1.5", p)), "0.03162277660168379331998893544432718533719555139325216826857504852792594438639238221344248108379300295187347284152840055148548856030");
+            check(&BigFloat::parse("3", p).powi(-5), "0.004115226337448559670781893004115226337448559670781893004115226337448559670781893004115226337448559670781893004115226337448559670782");
+            assert_eq!(two.pow(&BigFloat::parse("10", p)), BigFloat::parse("1024", p));
+        }
+    }
+}
@@ -1,16 +1,41 @@
-#![allow(unused)]
-mod complex;
-mod rational;
+pub mod complex;
+pub mod rational;
+pub mod big;
+mod ft;
+mod oper;
+pub mod fibb;
+pub mod float;
+pub mod decimal;
+pub mod func;
+pub mod consts;
+pub mod matrix;
+pub mod comb;
+pub mod prime;
+pub mod factor;
+pub mod modring;
+pub mod sieve;
+pub mod radix;
+pub mod convert;
+pub mod mixed;
+pub mod binary;
+pub mod random;
 
 use complex::Complex;
 use std::cmp::max;
 use std::f64::consts::PI;
-use std::fmt::{Debug, Display, Formatter};
-use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
+use std::fmt::Display;
+use std::ops::{Add, AddAssign, Mul, MulAssign};
 use std::sync::mpsc;
 use std::thread;
 use std::time::Duration;
 use rand::Rng;
+use lazy_static::lazy_static;
+use ft::RevCash;
+
+// Таблицы поразрядно обратных перестановок для БПФ размером до 2^22
+lazy_static!(
+    pub(crate) static ref REV_CASH: RevCash = RevCash::new(22);
+);
 
 //------------------------------------------------------------------------------------------------
 // Большие числа
@@ -30,10 +55,9 @@ impl Big {
     }
     // big -> string
     pub fn to_str(&self) -> String {
-        (&self.0).into_iter().rev()
-            .map(|x| x.to_string())
-            .reduce(|a, b| a + b.as_str())
-            .unwrap()
+        let mut s = String::with_capacity(self.0.len());
+        s.extend(self.0.iter().rev().map(|&x| (b'0' + x) as char));
+        s
     }
     // big -> vector<complex> for FFT
     pub fn to_complex(&self) -> Vec<Complex> {
@@ -55,9 +79,37 @@ impl Big {
 }
 
 // big + big -> big
+// сумма пишется на место левого операнда
 impl Add for Big {
     type Output = Big;
-    fn add(self, rhs: Self) -> Self::Output {
+    fn add(mut self, rhs: Self) -> Self::Output {
+        add_to(&mut self.0, &rhs.0);
+        self
+    }
+}
+
+// big + &big -> big
+impl Add<&Big> for Big {
+    type Output = Big;
+    fn add(mut self, rhs: &Big) -> Self::Output {
+        add_to(&mut self.0, &rhs.0);
+        self
+    }
+}
+
+// &big + big -> big
+impl Add<Big> for &Big {
+    type Output = Big;
+    fn add(self, mut rhs: Big) -> Self::Output {
+        add_to(&mut rhs.0, &self.0);
+        rhs
+    }
+}
+
+// &big + &big -> big
+impl Add<&Big> for &Big {
+    type Output = Big;
+    fn add(self, rhs: &Big) -> Self::Output {
         Big(add_vec(&self.0, &rhs.0))
     }
 }
@@ -65,7 +117,14 @@ impl Add for Big {
 // big += big
 impl AddAssign for Big {
     fn add_assign(&mut self, rhs: Self) {
-        self.0 = add_vec(&self.0, &rhs.0);
+        add_to(&mut self.0, &rhs.0);
+    }
+}
+
+// big += &big
+impl AddAssign<&Big> for Big {
+    fn add_assign(&mut self, rhs: &Big) {
+        add_to(&mut self.0, &rhs.0);
     }
 }
 
@@ -93,6 +152,44 @@ impl Mul<Big> for Big {
     }
 }
 
+// big * &big
+impl Mul<&Big> for Big {
+    type Output = Big;
+    fn mul(self, rhs: &Big) -> Self::Output {
+        Self(multiply(&self.0, &rhs.0))
+    }
+}
+
+// &big * big
+impl Mul<Big> for &Big {
+    type Output = Big;
+    fn mul(self, rhs: Big) -> Self::Output {
+        Big(multiply(&self.0, &rhs.0))
+    }
+}
+
+// &big * &big
+impl Mul<&Big> for &Big {
+    type Output = Big;
+    fn mul(self, rhs: &Big) -> Self::Output {
+        Big(multiply(&self.0, &rhs.0))
+    }
+}
+
+// big *= big
+impl MulAssign for Big {
+    fn mul_assign(&mut self, rhs: Self) {
+        self.0 = multiply(&self.0, &rhs.0);
+    }
+}
+
+// big *= &big
+impl MulAssign<&Big> for Big {
+    fn mul_assign(&mut self, rhs: &Big) {
+        self.0 = multiply(&self.0, &rhs.0);
+    }
+}
+
 impl PartialEq for Big {
     fn eq(&self, other: &Self) -> bool {
         let x = true;
@@ -148,8 +245,24 @@ fn add_vec(a: &Vec<u8>, b: &Vec<u8>) -> Vec<u8> {
     res
 }
 
+// Прибавить вектор на месте
+fn add_to(a: &mut Vec<u8>, b: &[u8]) {
+    if a.len() < b.len() { a.resize(b.len(), 0); }
+    let mut carry = 0;
+    let mut i = 0;
+    while i < b.len() || carry > 0 {
+        if i == a.len() { a.push(0); }
+        carry += a[i] + if i < b.len() { b[i] } else { 0 };
+        a[i] = carry % 10;
+        carry /= 10;
+        i += 1;
+    }
+}
+
 //------------------------------------------------------------------------------------------------
 // Вывод вектора на экран
+// отладочная функция, в коде не вызывается
+#[allow(dead_code)]
 fn print<T: Display>(name: &str, v: &Vec<T>) {
     print!("{}: ", name);
     let n = v.len();
@@ -158,6 +271,8 @@ fn print<T: Display>(name: &str, v: &Vec<T>) {
 }
 
 // vector<Complex> -> Vec<u32>
+// отладочная функция, в коде не вызывается
+#[allow(dead_code)]
 fn complex_vec_to_vec_u32(v: &Vec<Complex>) -> Vec<u32> {
     let mut w = Vec::new();
     for x in v {
@@ -169,6 +284,7 @@ fn complex_vec_to_vec_u32(v: &Vec<Complex>) -> Vec<u32> {
 //------------------------------------------------------------------------------------------------
 // Дискретное преобразование Фурье по определению
 // функция нужна как эталон для проверки разных быстрых реализаций
+#[allow(dead_code)]
 fn dft(a: &Vec<Complex>, rev: bool) -> Vec<Complex> {
     let l = a.len();
     let mut res = Vec::new();
@@ -351,10 +467,11 @@ fn sqr(a: &Vec<u8>) -> Vec<u8> {
 // на входе - размер чисел и кол-во тестов
 // возвращает минимальное значение времени умножения
 pub fn benchmark(num_size: usize, qa_num: u32) -> Duration {
-    let f = |x| if x { rand::thread_rng().gen_range(0u8..9u8) }
-        else { rand::thread_rng().gen_range(1u8..9u8) };
+    // старшая цифра (последняя в векторе) от 1 до 9, остальные от 0 до 9
+    let f = |x| if x { rand::thread_rng().gen_range(1u8..=9u8) }
+        else { rand::thread_rng().gen_range(0u8..=9u8) };
     let mut times = Vec::with_capacity(qa_num as usize);
-    for i in 0..qa_num {
+    for _ in 0..qa_num {
         let mut a: Vec<u8> = vec![0; num_size];
         let mut b: Vec<u8> = vec![0; num_size];
         let mut x: bool;
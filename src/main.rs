@@ -3,21 +3,7 @@
 //use rand::Rng;
 // mod rational;
 // use crate::rational::Ratio;
-mod big;
-mod ft;
-mod oper;
-mod complex;
-mod fibb;
-
-use std::collections::HashMap;
-use lazy_static::lazy_static;
-use crate::big::BigDecimal;
-use crate::fibb::fib;
-use crate::ft::{rev_swap, RevCash};
-
-lazy_static!(
-    pub static ref REV_CASH: RevCash = RevCash::new(22);
-);
+use bignum::fibb::fib;
 
 fn main() {
 
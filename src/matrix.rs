@@ -0,0 +1,287 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Mul, MulAssign};
+use rayon::prelude::*;
+use crate::big::BigDecimal;
+use crate::oper::{add_assign_vec, divmod_vec, from_u128, mul_vec, sqr};
+
+//-------------------------------------------------------------------------------------------------
+// Квадратные матрицы больших чисел k x k
+// элементы хранятся по строкам
+#[derive(Clone, PartialEq, Eq)]
+pub struct Matrix {
+    k: usize,
+    data: Vec<BigDecimal>
+}
+
+impl Matrix {
+    // нулевая матрица
+    pub fn zero(k: usize) -> Self {
+        Self { k, data: vec![BigDecimal::zero(); k * k] }
+    }
+    // единичная матрица
+    pub fn one(k: usize) -> Self {
+        let mut res = Self::zero(k);
+        for i in 0..k { res.data[i * k + i] = BigDecimal::one(); }
+        res
+    }
+    // матрица из строк, все строки должны быть длины rows.len()
+    pub fn from_rows(rows: Vec<Vec<BigDecimal>>) -> Self {
+        let k = rows.len();
+        if rows.iter().any(|r| r.len() != k) { panic!("matrix must be square"); }
+        Self { k, data: rows.into_iter().flatten().collect() }
+    }
+    pub fn size(&self) -> usize { self.k }
+    pub fn get(&self, i: usize, j: usize) -> &BigDecimal { &self.data[i * self.k + j] }
+    pub fn set(&mut self, i: usize, j: usize, x: BigDecimal) { self.data[i * self.k + j] = x }
+    // квадрат матрицы
+    pub fn sqr(&self) -> Self { mul_mat(self, self, None) }
+    // степень матрицы
+    pub fn pow(&self, p: u64) -> Self { pow(self, p, None) }
+    // степень матрицы, элементы берутся по модулю m
+    pub fn pow_mod(&self, p: u64, m: &BigDecimal) -> Self {
+        if m.is_zero() { panic!("zero modulus"); }
+        pow(&reduce_mat(self, m), p, Some(m))
+    }
+    // произведение, элементы берутся по модулю m
+    pub fn mul_mod(&self, rhs: &Self, m: &BigDecimal) -> Self {
+        if m.is_zero() { panic!("zero modulus"); }
+        mul_mat(&reduce_mat(self, m), &reduce_mat(rhs, m), Some(m))
+    }
+}
+
+// x mod m
+fn reduce(x: Vec<u8>, m: Option<&BigDecimal>) -> Vec<u8> {
+    match m {
+        Some(m) => divmod_vec(&x, &m.digits).1,
+        None => x
+    }
+}
+
+fn reduce_mat(a: &Matrix, m: &BigDecimal) -> Matrix {
+    Matrix {
+        k: a.k,
+        data: a.data.iter().map(|x| BigDecimal { digits: reduce(x.digits.clone(), Some(m)) }).collect()
+    }
+}
+
+fn reduce_mat_opt(a: Matrix, m: Option<&BigDecimal>) -> Matrix {
+    match m {
+        Some(m) => reduce_mat(&a, m),
+        None => a
+    }
+}
+
+// p * q (по модулю m, если он задан)
+fn mul_mat(p: &Matrix, q: &Matrix, m: Option<&BigDecimal>) -> Matrix {
+    if p.k != q.k { panic!("matrix sizes differ"); }
+    let k = p.k;
+    // элементы результата считаются независимо (потенциально параллельно)
+    let data = (0..k * k).into_par_iter()
+        .map(|ij| {
+            let (i, j) = (ij / k, ij % k);
+            let mut s = vec![0u8];
+            for l in 0..k {
+                let (a, b) = (&p.data[i * k + l].digits, &q.data[l * k + j].digits);
+                // диагональный элемент при возведении в квадрат
+                let x = if std::ptr::eq(a, b) { sqr(a) } else { mul_vec(a, b) };
+                add_assign_vec(&mut s, &x);
+            }
+            BigDecimal { digits: reduce(s, m) }
+        })
+        .collect();
+    Matrix { k, data }
+}
+
+// Вычисление степени матрицы по определению
+fn pow(mat: &Matrix, p: u64, m: Option<&BigDecimal>) -> Matrix {
+    if p == 0 { return reduce_mat_opt(Matrix::one(mat.k), m) }
+    let x = pow(mat, p >> 1, m);
+    let x = mul_mat(&x, &x, m);
+    if p & 1 == 0 { x } else { mul_mat(mat, &x, m) }
+}
+
+impl Mul for Matrix {
+    type Output = Matrix;
+    fn mul(self, rhs: Self) -> Self::Output {
+        mul_mat(&self, &rhs, None)
+    }
+}
+
+impl MulAssign for Matrix {
+    fn mul_assign(&mut self, rhs: Self) {
+        *self = mul_mat(self, &rhs, None);
+    }
+}
+
+impl Display for Matrix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, row) in self.data.chunks(self.k).enumerate() {
+            if i > 0 { writeln!(f)?; }
+            for (j, x) in row.iter().enumerate() {
+                if j > 0 { write!(f, " ")?; }
+                write!(f, "{x}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Линейные рекуррентные последовательности с постоянными коэффициентами
+// a(n) = c(1) a(n-1) + c(2) a(n-2) + ... + c(k) a(n-k), n >= k
+// коэффициенты и начальные значения a(0), ..., a(k-1) - неотрицательные целые
+#[derive(Clone)]
+pub struct LinearRecurrence {
+    coeffs: Vec<BigDecimal>,
+    initial: Vec<BigDecimal>,
+    modulus: Option<BigDecimal>
+}
+
+impl LinearRecurrence {
+    pub fn new(coeffs: Vec<BigDecimal>, initial: Vec<BigDecimal>) -> Self {
+        if coeffs.is_empty() { panic!("empty recurrence"); }
+        if coeffs.len() != initial.len() { panic!("need one initial value per coefficient"); }
+        Self { coeffs, initial, modulus: None }
+    }
+    // все члены последовательности берутся по модулю m
+    pub fn with_modulus(self, m: BigDecimal) -> Self {
+        if m.is_zero() { panic!("zero modulus"); }
+        Self { modulus: Some(m), ..self }
+    }
+    pub fn order(&self) -> usize { self.coeffs.len() }
+    pub fn modulus(&self) -> Option<&BigDecimal> { self.modulus.as_ref() }
+
+    // Числа Фибоначчи: 0, 1, 1, 2, 3, 5, ...
+    pub fn fibonacci() -> Self { Self::small(&[1, 1], &[0, 1]) }
+    // Числа Люка: 2, 1, 3, 4, 7, 11, ...
+    pub fn lucas() -> Self { Self::small(&[1, 1], &[2, 1]) }
+    // Числа Пелля: 0, 1, 2, 5, 12, 29, ...
+    pub fn pell() -> Self { Self::small(&[2, 1], &[0, 1]) }
+    // Числа трибоначчи: 0, 0, 1, 1, 2, 4, 7, ...
+    pub fn tribonacci() -> Self { Self::small(&[1, 1, 1], &[0, 0, 1]) }
+
+    fn small(coeffs: &[u64], initial: &[u64]) -> Self {
+        let big = |v: &[u64]| v.iter().map(|&x| BigDecimal { digits: from_u128(x as u128) }).collect();
+        Self::new(big(coeffs), big(initial))
+    }
+
+    // Сопровождающая матрица: переводит (a(n-1), ..., a(n-k)) в (a(n), ..., a(n-k+1))
+    pub fn companion(&self) -> Matrix {
+        let k = self.order();
+        let mut res = Matrix::zero(k);
+        for (j, c) in self.coeffs.iter().enumerate() { res.set(0, j, c.clone()); }
+        for i in 1..k { res.set(i, i - 1, BigDecimal::one()); }
+        res
+    }
+
+    // n-й член последовательности
+    pub fn term(&self, n: u64) -> BigDecimal {
+        let k = self.order();
+        let m = self.modulus.as_ref();
+        if n < k as u64 {
+            return BigDecimal { digits: reduce(self.initial[n as usize].digits.clone(), m) }
+        }
+        let c = self.companion();
+        let p = n - k as u64 + 1;
+        let c = match m {
+            Some(m) => c.pow_mod(p, m),
+            None => c.pow(p)
+        };
+        // a(n) = первая строка C^p * (a(k-1), ..., a(0))
+        let mut s = vec![0u8];
+        for j in 0..k {
+            add_assign_vec(&mut s, &mul_vec(&c.get(0, j).digits, &self.initial[k - 1 - j].digits));
+        }
+        BigDecimal { digits: reduce(s, m) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fibb::{fib, lucas, pell};
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    fn small(rows: &[&[u64]]) -> Matrix {
+        Matrix::from_rows(rows.iter().map(|r| r.iter().map(|&x| big(&x.to_string())).collect()).collect())
+    }
+
+    #[test]
+    fn fibonacci_by_matrix() {
+        // (1 1; 1 0)^n = (F(n+1) F(n); F(n) F(n-1))
+        let q = small(&[&[1, 1], &[1, 0]]);
+        for n in [1u64, 2, 10, 100, 1000] {
+            let x = q.pow(n);
+            assert_eq!(*x.get(0, 0), fib(n + 1), "n = {n}");
+            assert_eq!(*x.get(0, 1), fib(n), "n = {n}");
+            assert_eq!(*x.get(1, 0), fib(n), "n = {n}");
+            assert_eq!(*x.get(1, 1), fib(n - 1), "n = {n}");
+        }
+        assert!(q.pow(0) == Matrix::one(2));
+        assert!(q.sqr() == q.clone() * q.clone());
+        assert_eq!(q.pow(100).to_string(), format!("{} {}\n{} {}", fib(101), fib(100), fib(100), fib(99)));
+    }
+
+    #[test]
+    fn known_recurrences() {
+        let f = LinearRecurrence::fibonacci();
+        let l = LinearRecurrence::lucas();
+        let p = LinearRecurrence::pell();
+        for n in [0u64, 1, 2, 3, 10, 100, 777] {
+            assert_eq!(f.term(n), fib(n), "n = {n}");
+            assert_eq!(l.term(n), lucas(n), "n = {n}");
+            assert_eq!(p.term(n), pell(n), "n = {n}");
+        }
+        let t = LinearRecurrence::tribonacci();
+        let first: Vec<String> = (0..10).map(|n| t.term(n).to_string()).collect();
+        assert_eq!(first, ["0", "0", "1", "1", "2", "4", "7", "13", "24", "44"]);
+        assert_eq!(t.term(10), big("81"));
+        assert_eq!(t.term(100), big("53324762928098149064722658"));
+    }
+
+    #[test]
+    fn user_defined_recurrence() {
+        // a(n) = a(n-1) + 2a(n-3), a(0) = 1, a(1) = 2, a(2) = 3
+        let r = LinearRecurrence::new(vec![big("1"), big("0"), big("2")], vec![big("1"), big("2"), big("3")]);
+        assert_eq!(r.order(), 3);
+        assert_eq!(r.term(2), big("3"));
+        assert_eq!(r.term(3), big("5"));
+        assert_eq!(r.term(300), big("669400450547454377960781834764435853520721982136174707115563266731993"));
+        let r = r.with_modulus(big("1000000007"));
+        assert_eq!(r.modulus(), Some(&big("1000000007")));
+        assert_eq!(r.term(300), big("743946890"));
+    }
+
+    #[test]
+    fn modular_terms() {
+        let m = big("100000000000000000000");
+        let f = LinearRecurrence::fibonacci().with_modulus(m.clone());
+        assert_eq!(f.term(1000), big("76137795166849228875"));
+        // начальные значения тоже берутся по модулю
+        let l = LinearRecurrence::lucas().with_modulus(big("2"));
+        assert_eq!(l.term(0), BigDecimal::zero());
+        // pow_mod и mul_mod согласованы с обычными операциями
+        let q = small(&[&[1, 1, 0], &[1, 0, 3], &[5, 2, 7]]);
+        let a = q.pow(40);
+        let b = q.pow_mod(40, &m);
+        for i in 0..3 {
+            for j in 0..3 {
+                assert_eq!(*b.get(i, j), a.get(i, j) % &m, "({i}, {j})");
+            }
+        }
+        assert!(q.mul_mod(&q, &m) == q.sqr());
+    }
+
+    #[test]
+    #[should_panic(expected = "matrix must be square")]
+    fn non_square_panics() {
+        Matrix::from_rows(vec![vec![big("1"), big("2")], vec![big("3")]]);
+    }
+
+    #[test]
+    #[should_panic(expected = "need one initial value per coefficient")]
+    fn wrong_initial_panics() {
+        LinearRecurrence::new(vec![big("1"), big("1")], vec![big("0")]);
+    }
+}
@@ -0,0 +1,337 @@
+use std::cmp::Ordering;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Rem, RemAssign, Sub, SubAssign};
+use crate::big::BigDecimal;
+use crate::oper::{add_assign_vec, add_vec, cmp_vec, divmod_u128, from_u128, mul_u128, sub_assign_vec, sub_vec};
+
+//-------------------------------------------------------------------------------------------------
+// Арифметика BigDecimal с примитивными целыми (в обе стороны)
+// примитив не переводится в BigDecimal: умножение и деление - один проход по цифрам;
+// BigDecimal неотрицательно, поэтому отрицательный результат - паника, как у вычитания
+
+// Знак и модуль примитивного целого
+pub trait Primitive: Copy {
+    fn split(self) -> (bool, u128);
+}
+
+macro_rules! primitive {
+    (unsigned: $($u:ty),*; signed: $($s:ty),*) => {
+        $(impl Primitive for $u {
+            fn split(self) -> (bool, u128) { (false, self as u128) }
+        })*
+        $(impl Primitive for $s {
+            fn split(self) -> (bool, u128) { (self < 0, self.unsigned_abs() as u128) }
+        })*
+    }
+}
+
+primitive!(unsigned: u8, u16, u32, u64, u128, usize; signed: i8, i16, i32, i64, i128, isize);
+
+// a + (-1)^neg * m
+fn add_signed(a: &[u8], neg: bool, m: u128) -> BigDecimal {
+    let m = from_u128(m);
+    BigDecimal { digits: if neg { sub_vec(a, &m) } else { add_vec(a, &m) } }
+}
+
+// a += (-1)^neg * m на месте, перенос и заем идут только по младшим цифрам
+fn add_assign_signed(a: &mut Vec<u8>, neg: bool, m: u128) {
+    let m = from_u128(m);
+    if neg { sub_assign_vec(a, &m) } else { add_assign_vec(a, &m) }
+}
+
+// (-1)^neg * m - a
+fn sub_from(neg: bool, m: u128, a: &[u8]) -> BigDecimal {
+    if neg && (m > 0 || a != [0u8]) { panic!("subtraction underflow"); }
+    BigDecimal { digits: sub_vec(&from_u128(m), a) }
+}
+
+// результат со знаком минус допустим, только если он равен нулю
+fn non_negative(neg: bool, x: BigDecimal) -> BigDecimal {
+    if neg && !x.is_zero() { panic!("negative result"); }
+    x
+}
+
+fn mul_signed(a: &[u8], neg: bool, m: u128) -> BigDecimal {
+    non_negative(neg, BigDecimal { digits: mul_u128(a, m) })
+}
+
+fn div_signed(a: &[u8], neg: bool, m: u128) -> BigDecimal {
+    if m == 0 { panic!("division by zero"); }
+    non_negative(neg, BigDecimal { digits: divmod_u128(a, m).0 })
+}
+
+// остаток имеет знак делимого, а BigDecimal неотрицательно
+fn rem_signed(a: &[u8], m: u128) -> BigDecimal {
+    if m == 0 { panic!("division by zero"); }
+    BigDecimal { digits: from_u128(divmod_u128(a, m).1) }
+}
+
+// m / a и m % a, примитив слева
+fn divmod_rev(m: u128, a: &[u8]) -> (u128, u128) {
+    if a == [0u8] { panic!("division by zero"); }
+    if cmp_vec(&from_u128(m), a) == Ordering::Less { return (0, m) }
+    let d = BigDecimal { digits: a.to_vec() };
+    let d = u128::try_from(&d).unwrap();
+    (m / d, m % d)
+}
+
+macro_rules! mixed_ops {
+    ($($t:ty),*) => {$(
+        impl Add<$t> for BigDecimal {
+            type Output = BigDecimal;
+            fn add(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                add_signed(&self.digits, neg, m)
+            }
+        }
+
+        impl Add<BigDecimal> for $t {
+            type Output = BigDecimal;
+            fn add(self, rhs: BigDecimal) -> BigDecimal { rhs + self }
+        }
+
+        impl Add<$t> for &BigDecimal {
+            type Output = BigDecimal;
+            fn add(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                add_signed(&self.digits, neg, m)
+            }
+        }
+
+        impl AddAssign<$t> for BigDecimal {
+            fn add_assign(&mut self, rhs: $t) {
+                let (neg, m) = rhs.split();
+                add_assign_signed(&mut self.digits, neg, m);
+            }
+        }
+
+        impl Sub<$t> for BigDecimal {
+            type Output = BigDecimal;
+            fn sub(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                add_signed(&self.digits, !neg, m)
+            }
+        }
+
+        impl Sub<BigDecimal> for $t {
+            type Output = BigDecimal;
+            fn sub(self, rhs: BigDecimal) -> BigDecimal {
+                let (neg, m) = self.split();
+                sub_from(neg, m, &rhs.digits)
+            }
+        }
+
+        impl Sub<$t> for &BigDecimal {
+            type Output = BigDecimal;
+            fn sub(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                add_signed(&self.digits, !neg, m)
+            }
+        }
+
+        impl SubAssign<$t> for BigDecimal {
+            fn sub_assign(&mut self, rhs: $t) {
+                let (neg, m) = rhs.split();
+                add_assign_signed(&mut self.digits, !neg, m);
+            }
+        }
+
+        impl Mul<$t> for BigDecimal {
+            type Output = BigDecimal;
+            fn mul(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                mul_signed(&self.digits, neg, m)
+            }
+        }
+
+        impl Mul<BigDecimal> for $t {
+            type Output = BigDecimal;
+            fn mul(self, rhs: BigDecimal) -> BigDecimal { rhs * self }
+        }
+
+        impl Mul<$t> for &BigDecimal {
+            type Output = BigDecimal;
+            fn mul(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                mul_signed(&self.digits, neg, m)
+            }
+        }
+
+        impl MulAssign<$t> for BigDecimal {
+            fn mul_assign(&mut self, rhs: $t) {
+                let (neg, m) = rhs.split();
+                *self = mul_signed(&self.digits, neg, m);
+            }
+        }
+
+        impl Div<$t> for BigDecimal {
+            type Output = BigDecimal;
+            fn div(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                div_signed(&self.digits, neg, m)
+            }
+        }
+
+        impl Div<BigDecimal> for $t {
+            type Output = BigDecimal;
+            fn div(self, rhs: BigDecimal) -> BigDecimal {
+                let (neg, m) = self.split();
+                non_negative(neg, BigDecimal::from(divmod_rev(m, &rhs.digits).0))
+            }
+        }
+
+        impl Div<$t> for &BigDecimal {
+            type Output = BigDecimal;
+            fn div(self, rhs: $t) -> BigDecimal {
+                let (neg, m) = rhs.split();
+                div_signed(&self.digits, neg, m)
+            }
+        }
+
+        impl DivAssign<$t> for BigDecimal {
+            fn div_assign(&mut self, rhs: $t) {
+                let (neg, m) = rhs.split();
+                *self = div_signed(&self.digits, neg, m);
+            }
+        }
+
+        impl Rem<$t> for BigDecimal {
+            type Output = BigDecimal;
+            fn rem(self, rhs: $t) -> BigDecimal {
+                rem_signed(&self.digits, rhs.split().1)
+            }
+        }
+
+        impl Rem<BigDecimal> for $t {
+            type Output = BigDecimal;
+            fn rem(self, rhs: BigDecimal) -> BigDecimal {
+                let (neg, m) = self.split();
+                non_negative(neg, BigDecimal::from(divmod_rev(m, &rhs.digits).1))
+            }
+        }
+
+        impl Rem<$t> for &BigDecimal {
+            type Output = BigDecimal;
+            fn rem(self, rhs: $t) -> BigDecimal {
+                rem_signed(&self.digits, rhs.split().1)
+            }
+        }
+
+        impl RemAssign<$t> for BigDecimal {
+            fn rem_assign(&mut self, rhs: $t) {
+                *self = rem_signed(&self.digits, rhs.split().1);
+            }
+        }
+    )*}
+}
+
+mixed_ops!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    #[test]
+    fn add_sub_both_orders() {
+        let x = big("1000000000000000000000");
+        assert_eq!(&x + 5u8, big("1000000000000000000005"));
+        assert_eq!(5u8 + x.clone(), big("1000000000000000000005"));
+        assert_eq!(x.clone() + u128::MAX, big("340282366920938464463374607431768211455"));
+        assert_eq!(&x - 1u64, big("999999999999999999999"));
+        assert_eq!(u128::MAX - x.clone(), big("340282366920938462463374607431768211455"));
+        assert_eq!(7u32 - big("7"), BigDecimal::zero());
+        // отрицательный примитив: прибавление - вычитание модуля и наоборот
+        assert_eq!(&x + (-1i32), big("999999999999999999999"));
+        assert_eq!(-1i32 + x.clone(), big("999999999999999999999"));
+        assert_eq!(x.clone() - i128::MIN, big("170141183460469232731687303715884105728"));
+        assert_eq!(&x - (-5i8), big("1000000000000000000005"));
+        assert_eq!(0i64 - BigDecimal::zero(), BigDecimal::zero());
+    }
+
+    #[test]
+    fn add_sub_assign_in_place() {
+        let mut x = big("999999999999999999");
+        x += 1u8;
+        assert_eq!(x, big("1000000000000000000"));
+        x -= 1u8;
+        assert_eq!(x, big("999999999999999999"));
+        x += -999999999999999999i64;
+        assert_eq!(x, BigDecimal::zero());
+        x -= -12i16;
+        assert_eq!(x, big("12"));
+        x += u128::MAX;
+        assert_eq!(x, big("340282366920938463463374607431768211467"));
+        x -= u128::MAX;
+        assert_eq!(x, big("12"));
+        x -= 12usize;
+        assert_eq!(x, BigDecimal::zero());
+        // заем через длинную цепочку нулей
+        let mut y = big(&format!("1{}", "0".repeat(100)));
+        y -= 1i32;
+        assert_eq!(y, big(&"9".repeat(100)));
+        y += 1u64;
+        assert_eq!(y, big(&format!("1{}", "0".repeat(100))));
+    }
+
+    #[test]
+    fn mul_div_rem() {
+        let x = big("123456789012345678901234567890");
+        assert_eq!(&x * 10u8, big("1234567890123456789012345678900"));
+        assert_eq!(10u8 * x.clone(), big("1234567890123456789012345678900"));
+        let zero = 0i32;
+        assert_eq!(&x * zero, BigDecimal::zero());
+        assert_eq!(BigDecimal::zero() * -5i32, BigDecimal::zero());
+        assert_eq!(&x / 1000u16, big("123456789012345678901234567"));
+        assert_eq!(&x % 1000u16, big("890"));
+        // остаток имеет знак делимого
+        assert_eq!(&x % -1000i16, big("890"));
+        assert_eq!(1000u64 / big("7"), big("142"));
+        assert_eq!(1000u64 % big("7"), big("6"));
+        assert_eq!(5u8 / x.clone(), BigDecimal::zero());
+        assert_eq!(5u8 % x.clone(), big("5"));
+        let mut y = x.clone();
+        y *= 3u8;
+        y /= 3u8;
+        y %= 1_000_000u32;
+        assert_eq!(y, big("567890"));
+    }
+
+    #[test]
+    #[should_panic(expected = "subtraction underflow")]
+    fn sub_assign_negative_result() {
+        let mut x = big("5");
+        x -= 6u8;
+    }
+
+    #[test]
+    #[should_panic(expected = "subtraction underflow")]
+    fn add_assign_negative_primitive() {
+        let mut x = big("5");
+        x += -6i64;
+    }
+
+    #[test]
+    #[should_panic(expected = "subtraction underflow")]
+    fn sub_from_primitive_negative() {
+        let _ = 3u8 - big("4");
+    }
+
+    #[test]
+    #[should_panic(expected = "subtraction underflow")]
+    fn negative_primitive_minus_big() {
+        let _ = -1i8 - BigDecimal::zero();
+    }
+
+    #[test]
+    #[should_panic(expected = "negative result")]
+    fn mul_negative_result() {
+        let _ = big("2") * -1i32;
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn div_by_zero() {
+        let _ = big("2") / 0u8;
+    }
+}
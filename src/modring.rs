@@ -0,0 +1,354 @@
+use std::cmp::Ordering;
+use crate::big::BigDecimal;
+use crate::oper::{add_vec, cmp_vec, divmod_small, divmod_vec, gcd_vec, mul_vec, shl10, shr10, sqr, sub_vec, to_bits, trim_vec};
+
+//-------------------------------------------------------------------------------------------------
+// Вычет по модулю n в форме Монтгомери: x * R mod n
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Residue {
+    digits: Vec<u8>
+}
+
+impl Residue {
+    pub fn is_zero(&self) -> bool { self.digits == [0u8] }
+}
+
+// Кольцо вычетов по нечетному модулю n с умножением Монтгомери
+// R = 10^k > n, приведение по модулю R и деление на R - это сдвиги, деления на n не нужны;
+// если n делится на 5, R = 1 и вычеты приводятся обычным делением
+#[derive(Clone)]
+pub struct ModRing {
+    n: Vec<u8>,
+    k: usize,
+    // -n^(-1) mod R
+    n_inv: Vec<u8>,
+    // R mod n (единица кольца) и R^2 mod n
+    one: Vec<u8>,
+    r2: Vec<u8>
+}
+
+impl ModRing {
+    pub fn new(n: &BigDecimal) -> Self {
+        let n = n.digits.clone();
+        if n[0].is_multiple_of(2) { panic!("modulus must be odd"); }
+        if n[0] == 5 {
+            let one = divmod_vec(&[1], &n).1;
+            return Self { n, k: 0, n_inv: vec![0u8], one: one.clone(), r2: one }
+        }
+        let k = n.len();
+        let n_inv = sub_vec(&shl10(&[1], k), &inv_pow10(&n, k));
+        let one = divmod_vec(&shl10(&[1], k), &n).1;
+        let r2 = divmod_vec(&shl10(&[1], 2 * k), &n).1;
+        Self { n, k, n_inv, one, r2 }
+    }
+    pub fn modulus(&self) -> BigDecimal { BigDecimal { digits: self.n.clone() } }
+
+    // Редукция Монтгомери: t / R mod n для t < n * R
+    fn redc(&self, t: &[u8]) -> Vec<u8> {
+        if self.k == 0 { return divmod_vec(t, &self.n).1 }
+        // m = (t mod R) * (-n^(-1)) mod R, тогда t + m * n делится на R
+        let m = low(&mul_vec(&low(t, self.k), &self.n_inv), self.k);
+        let u = shr10(&add_vec(t, &mul_vec(&m, &self.n)), self.k);
+        if cmp_vec(&u, &self.n) == Ordering::Less { u } else { sub_vec(&u, &self.n) }
+    }
+
+    // Перевод в форму Монтгомери и обратно
+    pub fn residue(&self, x: &BigDecimal) -> Residue {
+        let x = divmod_vec(&x.digits, &self.n).1;
+        Residue { digits: self.redc(&mul_vec(&x, &self.r2)) }
+    }
+    pub fn value(&self, a: &Residue) -> BigDecimal {
+        BigDecimal { digits: self.redc(&a.digits) }
+    }
+
+    // НОД(a, n), множитель R на него не влияет
+    pub fn gcd(&self, a: &Residue) -> BigDecimal {
+        BigDecimal { digits: gcd_vec(&a.digits, &self.n) }
+    }
+
+    pub fn zero(&self) -> Residue { Residue { digits: vec![0u8] } }
+    pub fn one(&self) -> Residue { Residue { digits: self.one.clone() } }
+
+    // a + b
+    pub fn add(&self, a: &Residue, b: &Residue) -> Residue {
+        let c = add_vec(&a.digits, &b.digits);
+        Residue { digits: if cmp_vec(&c, &self.n) == Ordering::Less { c } else { sub_vec(&c, &self.n) } }
+    }
+    // a - b
+    pub fn sub(&self, a: &Residue, b: &Residue) -> Residue {
+        Residue {
+            digits: match cmp_vec(&a.digits, &b.digits) {
+                Ordering::Less => sub_vec(&add_vec(&a.digits, &self.n), &b.digits),
+                _ => sub_vec(&a.digits, &b.digits)
+            }
+        }
+    }
+    // -a
+    pub fn neg(&self, a: &Residue) -> Residue {
+        self.sub(&self.zero(), a)
+    }
+    // a * b
+    pub fn mul(&self, a: &Residue, b: &Residue) -> Residue {
+        Residue { digits: self.redc(&mul_vec(&a.digits, &b.digits)) }
+    }
+    // a^2
+    pub fn sqr(&self, a: &Residue) -> Residue {
+        Residue { digits: self.redc(&sqr(&a.digits)) }
+    }
+    // a^e
+    pub fn pow(&self, a: &Residue, e: &BigDecimal) -> Residue {
+        self.pow_bits(a, &to_bits(&e.digits))
+    }
+    // a^e, e задано двоичными цифрами начиная со старшей
+    // окна по 4 бита: на каждые 4 возведения в квадрат одно умножение
+    pub fn pow_bits(&self, a: &Residue, e: &[bool]) -> Residue {
+        let mut table = vec![self.one(), a.clone()];
+        for i in 2..16 { table.push(self.mul(&table[i - 1], a)); }
+        let mut res = self.one();
+        let head = e.len() % 4;
+        let windows = std::iter::once(&e[..head]).chain(e[head..].chunks(4));
+        for (i, w) in windows.enumerate() {
+            if i > 0 {
+                for _ in 0..4 { res = self.sqr(&res); }
+            }
+            let x = w.iter().fold(0, |acc, &bit| acc * 2 + bit as usize);
+            if x > 0 { res = self.mul(&res, &table[x]); }
+        }
+        res
+    }
+    // a^(-1) или None, если a не обратим
+    pub fn inv(&self, a: &Residue) -> Option<Residue> {
+        // расширенный алгоритм Евклида для x = a / R:
+        // x * t(i) = r(i) mod n, знаки t(i) чередуются, |t(i+1)| = |t(i-1)| + q(i) |t(i)|
+        let x = self.redc(&a.digits);
+        let (mut r0, mut r1) = (self.n.clone(), x);
+        let (mut t0, mut t1) = (vec![0u8], vec![1u8]);
+        let mut odd = false;
+        while r1 != [0u8] {
+            let (q, r) = divmod_vec(&r0, &r1);
+            (r0, r1) = (r1, r);
+            let t2 = add_vec(&t0, &mul_vec(&q, &t1));
+            (t0, t1) = (t1, t2);
+            odd = !odd;
+        }
+        if r0 != [1u8] { return None }
+        // t0 = t(i), при нечетном i положительно, иначе x^(-1) = n - |t(i)|
+        let t = divmod_vec(&t0, &self.n).1;
+        let t = if odd || t == [0u8] { t } else { sub_vec(&self.n, &t) };
+        // (x^(-1) mod n) * R = (x^(-1) * R^2) / R
+        Some(Residue { digits: self.redc(&mul_vec(&t, &self.r2)) })
+    }
+    // квадратный корень из a по простому модулю (алгоритм Тонелли-Шенкса)
+    // None, если a не квадратичный вычет или модуль оказался не простым
+    pub fn sqrt(&self, a: &Residue) -> Option<Residue> {
+        if a.is_zero() { return Some(self.zero()) }
+        let one = self.one();
+        let minus_one = self.neg(&one);
+        let n1 = sub_vec(&self.n, &[1]);
+        // критерий Эйлера
+        if self.pow_bits(a, &to_bits(&divmod_small(&n1, 2).0)) != one { return None }
+        // n - 1 = q * 2^s, q нечетное
+        let mut q = n1.clone();
+        let mut s = 0;
+        while q[0].is_multiple_of(2) {
+            q = divmod_small(&q, 2).0;
+            s += 1;
+        }
+        // z - квадратичный невычет; для простого n наименьший невычет
+        // меньше 2 ln(n)^2 (при обобщенной гипотезе Римана), иначе n составное
+        let limit = 2. * (self.n.len() as f64 * std::f64::consts::LN_10).powi(2);
+        let mut z = self.add(&one, &one);
+        let mut tries = 2.;
+        while self.pow_bits(&z, &to_bits(&divmod_small(&n1, 2).0)) != minus_one {
+            z = self.add(&z, &one);
+            tries += 1.;
+            if z.is_zero() || tries > limit { return None }
+        }
+        let mut c = self.pow_bits(&z, &to_bits(&q));
+        let mut r = self.pow_bits(a, &to_bits(&divmod_small(&add_vec(&q, &[1]), 2).0));
+        let mut t = self.pow_bits(a, &to_bits(&q));
+        let mut m = s;
+        while t != one {
+            // наименьшее i: t^(2^i) = 1
+            let mut i = 0;
+            let mut t2 = t.clone();
+            while t2 != one {
+                t2 = self.sqr(&t2);
+                i += 1;
+                if i == m { return None }
+            }
+            let mut b = c;
+            for _ in 0..m - i - 1 { b = self.sqr(&b); }
+            r = self.mul(&r, &b);
+            c = self.sqr(&b);
+            t = self.mul(&t, &c);
+            m = i;
+        }
+        if self.sqr(&r) == *a { Some(r) } else { None }
+    }
+}
+
+// Младшие k цифр числа: a mod 10^k
+fn low(a: &[u8], k: usize) -> Vec<u8> {
+    let mut res = a[..a.len().min(k)].to_vec();
+    trim_vec(&mut res);
+    res
+}
+
+// n^(-1) mod 10^k для n, взаимно простого с 10 (итерация Ньютона-Гензеля)
+// x = x * (2 - n * x) удваивает число верных младших цифр
+fn inv_pow10(n: &[u8], k: usize) -> Vec<u8> {
+    let d = n[0];
+    let mut x = vec![(1..10u8).find(|&x| (x * d) % 10 == 1).unwrap()];
+    let mut j = 1;
+    while j < k {
+        j = (2 * j).min(k);
+        let t = low(&mul_vec(&low(n, j), &x), j);
+        // 2 - t mod 10^j
+        let u = low(&sub_vec(&add_vec(&shl10(&[1], j), &[2]), &t), j);
+        x = low(&mul_vec(&x, &u), j);
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::oper::{from_u128, to_u128};
+    use crate::prime::{mul_mod_u64, pow_mod_u64};
+    use super::*;
+
+    fn big(x: u128) -> BigDecimal { BigDecimal { digits: from_u128(x) } }
+
+    // Модули меньше 2^64, в том числе делящиеся на 5 (R = 1)
+    const MODULI: [u64; 12] = [
+        3, 5, 7, 15, 25, 99, 125, 1001, 1_000_000_007, 999_999_999_999_999_875,
+        2_305_843_009_213_693_951, 18_446_744_073_709_551_557
+    ];
+
+    #[test]
+    fn redc_divides_by_r() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for n in MODULI {
+            let ring = ModRing::new(&big(n as u128));
+            // n * R - 1 - наибольшее допустимое t
+            let nr = mul_vec(&from_u128(n as u128), &shl10(&[1], ring.k));
+            let mut ts = vec![vec![0u8], sub_vec(&nr, &[1])];
+            ts.extend((0..100).map(|_| divmod_vec(&from_u128(rng.gen()), &nr).1));
+            for t in ts {
+                // redc(t) * R = t (mod n), redc(t) < n
+                let u = ring.redc(&t);
+                assert!(cmp_vec(&u, &ring.n).is_lt());
+                let diff = divmod_vec(&sub_vec(&add_vec(&shl10(&u, ring.k), &nr), &divmod_vec(&t, &ring.n).1), &ring.n).1;
+                assert_eq!(diff, [0u8], "n = {n}, t = {t:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn arithmetic_matches_u128() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for n in MODULI {
+            let ring = ModRing::new(&big(n as u128));
+            for _ in 0..200 {
+                let (x, y) = (rng.gen_range(0..n), rng.gen_range(0..n));
+                let (a, b) = (ring.residue(&big(x as u128)), ring.residue(&big(y as u128)));
+                let value = |r: Residue| to_u128(&ring.value(&r).digits) as u64;
+                assert_eq!(value(a.clone()), x);
+                assert_eq!(value(ring.add(&a, &b)), ((x as u128 + y as u128) % n as u128) as u64);
+                assert_eq!(value(ring.sub(&a, &b)), ((x as u128 + n as u128 - y as u128) % n as u128) as u64);
+                assert_eq!(value(ring.mul(&a, &b)), mul_mod_u64(x, y, n));
+                assert_eq!(value(ring.sqr(&a)), mul_mod_u64(x, x, n));
+            }
+            // вычет числа, большего модуля
+            assert_eq!(ring.value(&ring.residue(&big(u128::MAX))), big(u128::MAX % n as u128));
+        }
+    }
+
+    #[test]
+    fn pow_bits_matches_u128() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for n in MODULI {
+            let ring = ModRing::new(&big(n as u128));
+            // пустой показатель, неполное первое окно и показатели длиннее 64 бит
+            for bits in [0, 1, 3, 4, 5, 17, 64] {
+                let x = rng.gen_range(0..n);
+                let e: u64 = if bits == 64 { rng.gen() } else { rng.gen_range(0..1u64 << bits) };
+                let a = ring.residue(&big(x as u128));
+                let r = ring.pow(&a, &big(e as u128));
+                assert_eq!(ring.value(&r), big(pow_mod_u64(x, e, n) as u128), "{x}^{e} mod {n}");
+            }
+            let x = rng.gen_range(0..n);
+            let a = ring.residue(&big(x as u128));
+            let e: Vec<bool> = (0..130).map(|_| rng.gen()).collect();
+            // e = (hi * 2^64 + mid) * 2^64 + lo
+            let fold = |bits: &[bool]| bits.iter().fold(0u64, |acc, &b| acc * 2 + b as u64);
+            let mut expected = 1;
+            for part in [&e[..2], &e[2..66], &e[66..]] {
+                for _ in 0..part.len() { expected = mul_mod_u64(expected, expected, n); }
+                expected = mul_mod_u64(expected, pow_mod_u64(x, fold(part), n), n);
+            }
+            assert_eq!(ring.value(&ring.pow_bits(&a, &e)), big(expected as u128));
+        }
+    }
+
+    #[test]
+    fn inv() {
+        for n in [3u64, 5, 7, 15, 25, 99, 125, 1001] {
+            let ring = ModRing::new(&big(n as u128));
+            for x in 0..n {
+                let a = ring.residue(&big(x as u128));
+                let g = to_u128(&ring.gcd(&a).digits);
+                match ring.inv(&a) {
+                    Some(b) => {
+                        assert_eq!(g, 1, "{x} mod {n}");
+                        assert_eq!(ring.mul(&a, &b), ring.one(), "{x} mod {n}");
+                    }
+                    None => assert_ne!(g, 1, "{x} mod {n}")
+                }
+            }
+        }
+        // 2^127 - 1 и тот же модуль, умноженный на 5
+        let mut rng = StdRng::seed_from_u64(4);
+        let p = (1u128 << 127) - 1;
+        for n in [big(p), &big(p) * &big(5)] {
+            let ring = ModRing::new(&n);
+            for _ in 0..50 {
+                let a = ring.residue(&big(rng.gen()));
+                if ring.gcd(&a) != BigDecimal::one() { continue }
+                assert_eq!(ring.mul(&a, &ring.inv(&a).unwrap()), ring.one());
+            }
+        }
+        let ring = ModRing::new(&(&big(p) * &big(5)));
+        assert_eq!(ring.inv(&ring.residue(&big(p))), None);
+    }
+
+    #[test]
+    fn sqrt() {
+        let mut rng = StdRng::seed_from_u64(5);
+        // 5 - модуль с R = 1, 17, 97, 998244353 и 2^64 - 59 с разной степенью двойки в p - 1
+        let primes = [3u128, 5, 7, 13, 17, 97, 1_000_000_007, 998_244_353, 18_446_744_073_709_551_557, (1 << 127) - 1];
+        for p in primes {
+            let ring = ModRing::new(&big(p));
+            assert_eq!(ring.sqrt(&ring.zero()), Some(ring.zero()));
+            for _ in 0..20 {
+                let a = ring.residue(&big(rng.gen()));
+                let s = ring.sqr(&a);
+                let r = ring.sqrt(&s).unwrap();
+                assert!(r == a || r == ring.neg(&a), "mod {p}");
+                // -a^2 - невычет при p = 3 (mod 4)
+                if a.is_zero() { continue }
+                if let Some(r) = ring.sqrt(&ring.mul(&s, &ring.neg(&ring.one()))) {
+                    assert_eq!(p % 4, 1);
+                    assert_eq!(ring.sqr(&r), ring.neg(&s));
+                }
+            }
+        }
+        // 2 - невычет по модулю 5 и 13, 3 - по модулю 7 и 17
+        for (p, z) in [(5u128, 2u128), (13, 2), (7, 3), (17, 3)] {
+            let ring = ModRing::new(&big(p));
+            assert_eq!(ring.sqrt(&ring.residue(&big(z))), None, "{z} mod {p}");
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use std::cmp::Ordering;
 use rayon::prelude::*;
 use crate::ft::{fft, rev_swap};
 use crate::complex::Complex;
@@ -9,38 +10,56 @@ pub fn add_vec(arr_a: &[u8], arr_b: &[u8]) -> Vec<u8> {
     let v = [0u8].as_slice();
     if arr_a == v { return arr_b.to_vec() }
     if arr_b == v { return arr_a.to_vec() }
-    // во все тяжкие
-    if arr_a.len() >= arr_b.len() {
-        add_vec_loc(arr_a, arr_b)
-    } else {
-        add_vec_loc(arr_b, arr_a)
-    }
+    // копируем более длинное слагаемое с запасом под перенос и прибавляем к нему второе
+    let (a, b) = if arr_a.len() >= arr_b.len() { (arr_a, arr_b) } else { (arr_b, arr_a) };
+    let mut res = Vec::with_capacity(a.len() + 1);
+    res.extend_from_slice(a);
+    add_assign_vec(&mut res, b);
+    res
 }
 
-// a + b
-// len(a) >= len(b)
-fn add_vec_loc(a: &[u8], b: &[u8]) -> Vec<u8> {
-    let size_a = a.len();
-    let size_b = b.len();
-    let mut res = Vec::with_capacity(size_a + 1);
-    unsafe { res.set_len(size_a + 1) }
+// a += b на месте: a удлиняется только при необходимости,
+// после конца b перенос распространяется лишь до первой цифры меньше 9
+pub fn add_assign_vec(a: &mut Vec<u8>, b: &[u8]) {
+    if a.len() < b.len() { a.resize(b.len(), 0); }
     let mut carry = 0;
-    for i in 0..size_a {
-        carry += a[i] + if i < size_b { b[i] } else { 0 };
-        if carry > 9 {
-            res[i] = carry - 10;
+    for (x, &y) in a.iter_mut().zip(b) {
+        let s = *x + y + carry;
+        if s > 9 {
+            *x = s - 10;
             carry = 1;
         } else {
-            res[i] = carry;
+            *x = s;
+            carry = 0;
+        }
+    }
+    let mut i = b.len();
+    while carry > 0 {
+        if i == a.len() {
+            a.push(1);
+            break
+        }
+        if a[i] == 9 {
+            a[i] = 0;
+            i += 1;
+        } else {
+            a[i] += 1;
             carry = 0;
         }
     }
-    if carry > 0 {
-        res[size_a] = carry;
-    } else {
-        res.truncate(size_a);
-    };
-    res
+}
+
+// a += k на месте, перенос распространяется только до тех пор, пока он не нулевой
+pub fn add_small_vec(a: &mut Vec<u8>, k: u64) {
+    let mut carry = k as u128;
+    let mut i = 0;
+    while carry > 0 {
+        if i == a.len() { a.push(0); }
+        carry += a[i] as u128;
+        a[i] = (carry % 10) as u8;
+        carry /= 10;
+        i += 1;
+    }
 }
 
 // a * b
@@ -48,7 +67,7 @@ pub fn mul_vec(arr_a: &[u8], arr_b: &[u8]) -> Vec<u8> {
     // проверка умножения на 0
     let v0 = vec![0u8];
     let vs0 = v0.as_slice();
-    if arr_a == vs0 || arr_a == vs0 { return v0 }
+    if arr_a == vs0 || arr_b == vs0 { return v0 }
     // проверка умножения на 1
     let v = [1u8].as_slice();
     if arr_a == v { return arr_b.to_vec() }
@@ -121,9 +140,7 @@ pub fn sqr(arr_a: &[u8]) -> Vec<u8> {
 fn normalize(inp_arr: &[Complex], size: usize, src_size: usize) -> Vec<u8> {
     let mut res_size = (src_size + 1) << 1;
     if res_size > size { res_size = size };
-    let mut res = Vec::with_capacity(res_size);
-    unsafe { res.set_len(res_size) }
-    res.fill(0);
+    let mut res = vec![0u8; res_size];
     let mut carry = 0u32;
     let mut x;
     for i in 0..res_size {
@@ -140,3 +157,416 @@ fn normalize(inp_arr: &[Complex], size: usize, src_size: usize) -> Vec<u8> {
     }
     res
 }
+
+// Сравнение чисел a и b
+pub fn cmp_vec(a: &[u8], b: &[u8]) -> Ordering {
+    a.len().cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+// Убираем незначащие 0 в старших разрядах
+pub fn trim_vec(v: &mut Vec<u8>) {
+    while v.len() > 1 && v[v.len() - 1] == 0 { v.pop(); }
+    if v.is_empty() { v.push(0) }
+}
+
+// a - b
+// требование: a >= b
+pub fn sub_vec(arr_a: &[u8], arr_b: &[u8]) -> Vec<u8> {
+    if cmp_vec(arr_a, arr_b) == Ordering::Less {
+        panic!("subtraction underflow");
+    }
+    let mut res = arr_a.to_vec();
+    sub_vec_loc(&mut res, arr_b);
+    res
+}
+
+// a -= b на месте
+// требование: a >= b
+pub fn sub_assign_vec(a: &mut Vec<u8>, b: &[u8]) {
+    if cmp_vec(a, b) == Ordering::Less {
+        panic!("subtraction underflow");
+    }
+    sub_vec_loc(a, b);
+}
+
+// a -= b
+// len(a) >= len(b), a >= b
+fn sub_vec_loc(a: &mut Vec<u8>, b: &[u8]) {
+    let mut borrow = 0;
+    for i in 0..a.len() {
+        let x = if i < b.len() { b[i] } else if borrow == 0 { break } else { 0 };
+        let y = a[i] as i8 - x as i8 - borrow;
+        if y < 0 {
+            a[i] = (y + 10) as u8;
+            borrow = 1;
+        } else {
+            a[i] = y as u8;
+            borrow = 0;
+        }
+    }
+    trim_vec(a);
+}
+
+// a * k, где k < 10^17
+pub fn mul_small(a: &[u8], k: u64) -> Vec<u8> {
+    if k == 0 { return vec![0u8] }
+    let mut res = Vec::with_capacity(a.len() + 18);
+    let mut carry = 0u64;
+    for &x in a {
+        carry += x as u64 * k;
+        res.push((carry % 10) as u8);
+        carry /= 10;
+    }
+    while carry > 0 {
+        res.push((carry % 10) as u8);
+        carry /= 10;
+    }
+    trim_vec(&mut res);
+    res
+}
+
+// a / k, a % k, где 0 < k < 10^17
+pub fn divmod_small(a: &[u8], k: u64) -> (Vec<u8>, u64) {
+    if k == 0 { panic!("division by zero") }
+    let mut res = vec![0u8; a.len()];
+    let mut rem = 0u64;
+    for i in (0..a.len()).rev() {
+        rem = rem * 10 + a[i] as u64;
+        res[i] = (rem / k) as u8;
+        rem %= k;
+    }
+    trim_vec(&mut res);
+    (res, rem)
+}
+
+// a * k для любого k < 2^128 за один проход
+pub fn mul_u128(a: &[u8], k: u128) -> Vec<u8> {
+    if k < 100_000_000_000_000_000 { return mul_small(a, k as u64) }
+    // перенос не превосходит k, и carry + 9k < 2^128 при k < 10^37
+    if k >= 10_000_000_000_000_000_000_000_000_000_000_000_000 {
+        return add_vec(&shl10(&mul_u128(a, k / 10), 1), &mul_small(a, (k % 10) as u64))
+    }
+    let mut res = Vec::with_capacity(a.len() + 39);
+    let mut carry = 0u128;
+    for &x in a {
+        carry += x as u128 * k;
+        res.push((carry % 10) as u8);
+        carry /= 10;
+    }
+    while carry > 0 {
+        res.push((carry % 10) as u8);
+        carry /= 10;
+    }
+    trim_vec(&mut res);
+    res
+}
+
+// a / k, a % k для любого 0 < k < 2^128
+pub fn divmod_u128(a: &[u8], k: u128) -> (Vec<u8>, u128) {
+    if k < 100_000_000_000_000_000 {
+        let (q, r) = divmod_small(a, k as u64);
+        return (q, r as u128)
+    }
+    if k >= 10_000_000_000_000_000_000_000_000_000_000_000_000 {
+        let (q, r) = divmod_vec(a, &from_u128(k));
+        return (q, to_u128(&r))
+    }
+    // остаток меньше k < 10^37, rem * 10 + 9 помещается в u128
+    let mut res = vec![0u8; a.len()];
+    let mut rem = 0u128;
+    for i in (0..a.len()).rev() {
+        rem = rem * 10 + a[i] as u128;
+        res[i] = (rem / k) as u8;
+        rem %= k;
+    }
+    trim_vec(&mut res);
+    (res, rem)
+}
+
+// Число по его младшим цифрам (не более 38 цифр)
+pub fn to_u128(a: &[u8]) -> u128 {
+    a.iter().rev().fold(0u128, |acc, &x| acc * 10 + x as u128)
+}
+
+// Вектор цифр числа
+pub fn from_u128(mut x: u128) -> Vec<u8> {
+    let mut res = Vec::new();
+    while x > 0 {
+        res.push((x % 10) as u8);
+        x /= 10;
+    }
+    if res.is_empty() { res.push(0) }
+    res
+}
+
+// a * 10^k
+pub fn shl10(a: &[u8], k: usize) -> Vec<u8> {
+    if a == [0u8].as_slice() { return vec![0u8] }
+    let mut res = vec![0u8; k + a.len()];
+    res[k..].copy_from_slice(a);
+    res
+}
+
+// a / 10^k
+pub fn shr10(a: &[u8], k: usize) -> Vec<u8> {
+    if k >= a.len() { vec![0u8] } else { a[k..].to_vec() }
+}
+
+// a / b, a % b
+pub fn divmod_vec(arr_a: &[u8], arr_b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    if arr_b == [0u8].as_slice() { panic!("division by zero") }
+    if cmp_vec(arr_a, arr_b) == Ordering::Less {
+        return (vec![0u8], arr_a.to_vec())
+    }
+    // делитель помещается в u64
+    if arr_b.len() < 18 {
+        let (q, r) = divmod_small(arr_a, to_u128(arr_b) as u64);
+        return (q, from_u128(r as u128))
+    }
+    if arr_b.len() <= DIV_LIMIT || arr_a.len() - arr_b.len() <= DIV_LIMIT {
+        divmod_school(arr_a, arr_b)
+    } else {
+        divmod_newton(arr_a, arr_b)
+    }
+}
+
+// Размер, начиная с которого деление выполняется через обратную величину
+const DIV_LIMIT: usize = 64;
+
+// Деление "в столбик"
+// очередную цифру частного оцениваем по 18 старшим цифрам делителя
+fn divmod_school(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let m = b.len();
+    let t = m.min(18);
+    // оценка с недостатком
+    let b_top = to_u128(&b[m - t..]) + 1;
+    let mut q = vec![0u8; a.len()];
+    let mut r: Vec<u8> = Vec::with_capacity(m + 2);
+    for i in (0..a.len()).rev() {
+        // r = r * 10 + a[i]
+        r.insert(0, a[i]);
+        trim_vec(&mut r);
+        if cmp_vec(&r, b) == Ordering::Less { continue }
+        // r < 10 * b, поэтому в частном одна цифра
+        let mut d = (to_u128(&r[m - t..]) / b_top) as u8;
+        if d > 0 { sub_vec_loc(&mut r, &mul_small(b, d as u64)); }
+        while cmp_vec(&r, b) != Ordering::Less {
+            sub_vec_loc(&mut r, b);
+            d += 1;
+        }
+        q[i] = d;
+    }
+    trim_vec(&mut q);
+    trim_vec(&mut r);
+    (q, r)
+}
+
+// Деление через приближенную обратную величину делителя
+// с последующей коррекцией частного
+fn divmod_newton(a: &[u8], b: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let p = a.len() - b.len() + 3;
+    divmod_recip(a, b, &recip_vec(b, p), p)
+}
+
+// a / b, a % b по готовому приближению y ~ 10^(m - 1 + p) / b, m = len(b)
+// годится для всех a с len(a) <= m + p - 3, так что одно y служит многим делениям
+pub fn divmod_recip(a: &[u8], b: &[u8], y: &[u8], p: usize) -> (Vec<u8>, Vec<u8>) {
+    let m = b.len();
+    let mut q = shr10(&mul_vec(a, y), m - 1 + p);
+    let mut qb = mul_vec(&q, b);
+    while cmp_vec(&qb, a) == Ordering::Greater {
+        sub_vec_loc(&mut q, &[1]);
+        sub_vec_loc(&mut qb, b);
+    }
+    let mut r = sub_vec(a, &qb);
+    while cmp_vec(&r, b) != Ordering::Less {
+        sub_vec_loc(&mut r, b);
+        add_small_vec(&mut q, 1);
+    }
+    (q, r)
+}
+
+// Приближение к 10^(m - 1 + p) / b, где m = len(b), с точностью до нескольких единиц
+// Итерация Ньютона y = y + y * (10^k - b * y) / 10^k удваивает число верных цифр
+pub fn recip_vec(b: &[u8], p: usize) -> Vec<u8> {
+    let m = b.len();
+    // для p верных цифр достаточно p + 2 старших цифр делителя
+    if m > p + 2 { return recip_vec(&b[m - p - 2..], p) }
+    let k = m - 1 + p;
+    if p <= 2 * DIV_LIMIT { return divmod_school(&shl10(&[1], k), b).0 }
+    let h = p / 2 + 1;
+    let y = shl10(&recip_vec(b, h), p - h);
+    let by = mul_vec(b, &y);
+    let one = shl10(&[1], k);
+    match cmp_vec(&by, &one) {
+        Ordering::Equal => y,
+        Ordering::Less => {
+            let e = sub_vec(&one, &by);
+            add_vec(&y, &shr10(&mul_vec(&y, &e), k))
+        },
+        Ordering::Greater => {
+            let e = sub_vec(&by, &one);
+            sub_vec(&y, &shr10(&mul_vec(&y, &e), k))
+        }
+    }
+}
+
+// floor(sqrt(a))
+pub fn isqrt_vec(a: &[u8]) -> Vec<u8> {
+    if a.len() < 32 {
+        return from_u128(to_u128(a).isqrt())
+    }
+    // начальное приближение с избытком по 30-31 старшим цифрам
+    let s = (a.len() - 30) & !1;
+    let top = (to_u128(&a[s..]).isqrt() + 2) as u64;
+    let mut x = shl10(&mul_small(&[1], top), s / 2);
+    // итерации Ньютона x = (x + a / x) / 2 монотонно убывают к ответу
+    loop {
+        let y = divmod_small(&add_vec(&x, &divmod_vec(a, &x).0), 2).0;
+        if cmp_vec(&y, &x) != Ordering::Less { return x }
+        x = y;
+    }
+}
+
+// Наибольший общий делитель (алгоритм Евклида)
+pub fn gcd_vec(a: &[u8], b: &[u8]) -> Vec<u8> {
+    let (mut a, mut b) = (a.to_vec(), b.to_vec());
+    while b != [0u8] {
+        let r = divmod_vec(&a, &b).1;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+// Двоичные цифры числа, начиная со старшей
+pub fn to_bits(a: &[u8]) -> Vec<bool> {
+    let mut res = Vec::new();
+    let mut a = a.to_vec();
+    while a != [0u8] {
+        let (q, r) = divmod_small(&a, 1 << 56);
+        res.extend((0..56).map(|i| (r >> i) & 1 == 1));
+        a = q;
+    }
+    while res.last() == Some(&false) { res.pop(); }
+    res.reverse();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use super::*;
+
+    // Случайное n-значное число
+    fn random_vec(rng: &mut StdRng, n: usize) -> Vec<u8> {
+        let mut v: Vec<u8> = (0..n).map(|_| rng.gen_range(0..10)).collect();
+        v[n - 1] = rng.gen_range(1..10);
+        v
+    }
+
+    // Случайное число до 38 цифр со случайным числом значащих битов
+    fn random_u128(rng: &mut StdRng) -> u128 {
+        rng.gen_range(0..10u128.pow(38)) >> rng.gen_range(0..127)
+    }
+
+    // a = q * b + r, 0 <= r < b
+    fn check_divmod(a: &[u8], b: &[u8], (q, r): (Vec<u8>, Vec<u8>)) {
+        assert_eq!(add_vec(&mul_vec(&q, b), &r), a);
+        assert_eq!(cmp_vec(&r, b), Ordering::Less);
+    }
+
+    #[test]
+    fn divmod_matches_u128() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20000 {
+            let a = random_u128(&mut rng);
+            let b = random_u128(&mut rng).max(1);
+            let (q, r) = divmod_vec(&from_u128(a), &from_u128(b));
+            assert_eq!((to_u128(&q), to_u128(&r)), (a / b, a % b), "{a} / {b}");
+        }
+    }
+
+    #[test]
+    fn divmod_around_newton_limit() {
+        // по обе стороны DIV_LIMIT и по длине делителя, и по длине частного
+        let mut rng = StdRng::seed_from_u64(2);
+        for m in [18, DIV_LIMIT - 1, DIV_LIMIT, DIV_LIMIT + 1, 3 * DIV_LIMIT] {
+            for extra in [0, DIV_LIMIT - 1, DIV_LIMIT, DIV_LIMIT + 1, 3 * DIV_LIMIT] {
+                let a = random_vec(&mut rng, m + extra);
+                let b = random_vec(&mut rng, m);
+                check_divmod(&a, &b, divmod_vec(&a, &b));
+                if a.len() > b.len() {
+                    assert_eq!(divmod_newton(&a, &b), divmod_school(&a, &b), "{} / {}", a.len(), b.len());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn divmod_corrections() {
+        // точное деление, наибольший остаток и делители вида 10^k, 10^k - 1
+        let mut rng = StdRng::seed_from_u64(3);
+        let nines = vec![9u8; DIV_LIMIT + 10];
+        let power = shl10(&[1], DIV_LIMIT + 10);
+        for b in [random_vec(&mut rng, DIV_LIMIT + 10), nines, power] {
+            let q = random_vec(&mut rng, 2 * DIV_LIMIT);
+            let a = mul_vec(&q, &b);
+            assert_eq!(divmod_vec(&a, &b), (q.clone(), vec![0u8]));
+            assert_eq!(divmod_newton(&a, &b), (q.clone(), vec![0u8]));
+            let r = sub_vec(&b, &[1]);
+            let a = add_vec(&a, &r);
+            assert_eq!(divmod_vec(&a, &b), (q.clone(), r.clone()));
+            assert_eq!(divmod_newton(&a, &b), (q, r));
+        }
+    }
+
+    #[test]
+    fn recip_is_close_to_quotient() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for p in [5, 2 * DIV_LIMIT, 2 * DIV_LIMIT + 1, 700] {
+            for m in [20, p + 2, p + 3, p + 50] {
+                let b = random_vec(&mut rng, m);
+                let y = recip_vec(&b, p);
+                let exact = divmod_school(&shl10(&[1], m - 1 + p), &b).0;
+                let diff = if cmp_vec(&y, &exact).is_lt() { sub_vec(&exact, &y) } else { sub_vec(&y, &exact) };
+                assert!(diff.len() == 1, "p = {p}, m = {m}, diff = {diff:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn isqrt_matches_u128() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..20000 {
+            let a = random_u128(&mut rng);
+            assert_eq!(to_u128(&isqrt_vec(&from_u128(a))), a.isqrt(), "{a}");
+        }
+        // точные квадраты и соседние с ними числа, в том числе 32-38 цифр (через итерации Ньютона)
+        for _ in 0..2000 {
+            let x = rng.gen_range(1..10u128.pow(19));
+            let a = x * x;
+            assert_eq!(to_u128(&isqrt_vec(&from_u128(a))), x);
+            assert_eq!(to_u128(&isqrt_vec(&from_u128(a - 1))), x - 1);
+            assert_eq!(to_u128(&isqrt_vec(&from_u128(a + 2 * x))), x);
+        }
+    }
+
+    #[test]
+    fn isqrt_large() {
+        let mut rng = StdRng::seed_from_u64(6);
+        for n in [39, 40, 41, 100, 301, 1000] {
+            let a = random_vec(&mut rng, n);
+            let x = isqrt_vec(&a);
+            let next = add_vec(&x, &[1]);
+            assert!(cmp_vec(&mul_vec(&x, &x), &a).is_le());
+            assert!(cmp_vec(&mul_vec(&next, &next), &a).is_gt());
+            let x = random_vec(&mut rng, n);
+            let a = mul_vec(&x, &x);
+            assert_eq!(isqrt_vec(&a), x);
+            assert_eq!(isqrt_vec(&sub_vec(&a, &[1])), sub_vec(&x, &[1]));
+        }
+    }
+}
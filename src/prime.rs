@@ -0,0 +1,423 @@
+use std::cmp::Ordering;
+use rand::Rng;
+use crate::big::BigDecimal;
+use crate::sieve::primes_upto;
+use crate::modring::{ModRing, Residue};
+use crate::oper::{add_vec, cmp_vec, divmod_small, divmod_vec, from_u128, isqrt_vec, sqr, sub_vec, to_bits, to_u128};
+
+// Граница пробного деления перед вероятностными тестами
+const TRIAL_LIMIT: u64 = 1000;
+
+// Основания, при которых тест Миллера-Рабина точен для всех n < 2^64
+const BASES_64: [u64; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+// Ниже этой границы соседние простые ищутся перебором, выше - решетом по окнам
+const SCAN_LIMIT: u64 = 100_000_000;
+
+// Длина окна и граница простых для решета при поиске соседних простых
+const WINDOW: usize = 4096;
+const WINDOW_PRIMES: u64 = 10_000;
+
+//-------------------------------------------------------------------------------------------------
+// Результат проверки на простоту
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Primality {
+    // число доказанно простое
+    Prime,
+    // число прошло вероятностный тест
+    ProbablyPrime,
+    // число доказанно составное
+    Composite
+}
+
+// Проверка на простоту: пробное деление, затем для n < 2^64 детерминированный
+// тест Миллера-Рабина, а для больших n - тест Бейли-Померанса-Селфриджа-Вагстаффа
+pub fn is_prime(n: &BigDecimal) -> Primality {
+    let n = &n.digits;
+    if let Some(x) = to_u64(n) { return is_prime_u64(x) }
+    if small_factor(n, TRIAL_LIMIT).is_some() { return Primality::Composite }
+    bpsw(n)
+}
+
+// Тест Миллера-Рабина со случайными основаниями
+// для n < 2^64 число раундов не важно, результат точный
+pub fn miller_rabin(n: &BigDecimal, rounds: usize) -> Primality {
+    let n = &n.digits;
+    if let Some(x) = to_u64(n) { return is_prime_u64(x) }
+    if n[0].is_multiple_of(2) || n[0] == 5 { return Primality::Composite }
+    let mr = MillerRabin::new(n);
+    let mut rng = rand::thread_rng();
+    // основание a из [2, n - 2]
+    let range = sub_vec(n, &[3]);
+    for _ in 0..rounds {
+        let r: Vec<u8> = (0..n.len() + 8).map(|_| rng.gen_range(0..10)).collect();
+        let a = add_vec(&divmod_vec(&r, &range).1, &[2]);
+        if !mr.test(&a) { return Primality::Composite }
+    }
+    Primality::ProbablyPrime
+}
+
+// Тест Бейли-Померанса-Селфриджа-Вагстаффа:
+// сильный тест Ферма по основанию 2 и сильный тест Люка
+// составных чисел, проходящих его, не известно
+pub fn baillie_psw(n: &BigDecimal) -> Primality {
+    let n = &n.digits;
+    if let Some(x) = to_u64(n) { return is_prime_u64(x) }
+    bpsw(n)
+}
+
+// Наименьший простой делитель n, не больший bound и меньший n
+pub fn trial_division(n: &BigDecimal, bound: u64) -> Option<u64> {
+    small_factor(&n.digits, bound)
+}
+
+fn small_factor(n: &[u8], bound: u64) -> Option<u64> {
+    let primes = primes_upto(bound);
+    // остаток от деления на произведение нескольких простых, затем на каждое из них
+    let mut i = 0;
+    while i < primes.len() {
+        let mut m = 1u64;
+        let mut j = i;
+        while j < primes.len() && m < 100_000_000_000_000 / primes[j] {
+            m *= primes[j];
+            j += 1;
+        }
+        let (_, r) = divmod_small(n, m);
+        if let Some(&p) = primes[i..j].iter().find(|&&p| r.is_multiple_of(p)) {
+            if cmp_vec(n, &from_u128(p as u128)) == Ordering::Greater { return Some(p) }
+            return None
+        }
+        i = j;
+    }
+    None
+}
+
+fn bpsw(n: &[u8]) -> Primality {
+    if n[0].is_multiple_of(2) || n[0] == 5 { return Primality::Composite }
+    if !MillerRabin::new(n).test(&[2]) { return Primality::Composite }
+    if !strong_lucas(n) { return Primality::Composite }
+    Primality::ProbablyPrime
+}
+
+pub fn to_u64(n: &[u8]) -> Option<u64> {
+    if n.len() > 20 { return None }
+    u64::try_from(to_u128(n)).ok()
+}
+
+//-------------------------------------------------------------------------------------------------
+// Поиск простых чисел
+
+// Наименьшее простое, большее n
+pub fn next_prime(n: &BigDecimal) -> BigDecimal {
+    BigDecimal { digits: neighbour(&n.digits, true) }
+}
+
+// Наибольшее простое, меньшее n
+pub fn prev_prime(n: &BigDecimal) -> BigDecimal {
+    if cmp_vec(&n.digits, &[2]) != Ordering::Greater { panic!("no prime below {n}"); }
+    BigDecimal { digits: neighbour(&n.digits, false) }
+}
+
+// Случайное простое число из digits десятичных цифр (равномерно среди таких простых)
+pub fn random_prime(digits: usize) -> BigDecimal {
+    if digits == 0 { panic!("prime must have at least one digit"); }
+    let mut rng = rand::thread_rng();
+    loop {
+        let mut x: Vec<u8> = (0..digits).map(|_| rng.gen_range(0..10)).collect();
+        x[digits - 1] = rng.gen_range(1..10);
+        // многозначное простое оканчивается на 1, 3, 7 или 9
+        if digits > 1 { x[0] = [1, 3, 7, 9][rng.gen_range(0..4)]; }
+        let x = BigDecimal { digits: x };
+        if is_prime(&x) != Primality::Composite { return x }
+    }
+}
+
+// Ближайшее простое выше (up) или ниже n
+fn neighbour(n: &[u8], up: bool) -> Vec<u8> {
+    if let Some(x) = to_u64(n).filter(|&x| x < SCAN_LIMIT) {
+        let prime = |&p: &u64| is_prime_u64(p) == Primality::Prime;
+        let p = if up { (x + 1..).find(prime) } else { (2..x).rev().find(prime) };
+        return from_u128(p.unwrap() as u128)
+    }
+    // кандидаты base, base ± 1, ..., base ± (WINDOW - 1); все они больше WINDOW_PRIMES,
+    // поэтому вычеркиваются только составные
+    let primes = primes_upto(WINDOW_PRIMES);
+    let mut base = if up { add_vec(n, &[1]) } else { sub_vec(n, &[1]) };
+    loop {
+        let mut sieve = vec![false; WINDOW];
+        for &p in &primes {
+            let r = divmod_small(&base, p).1;
+            let first = if up { (p - r) % p } else { r };
+            for i in (first as usize..WINDOW).step_by(p as usize) { sieve[i] = true; }
+        }
+        for i in (0..WINDOW).filter(|&i| !sieve[i]) {
+            let d = from_u128(i as u128);
+            let c = if up { add_vec(&base, &d) } else { sub_vec(&base, &d) };
+            let res = match to_u64(&c) {
+                Some(x) => is_prime_u64(x),
+                None => bpsw(&c)
+            };
+            if res != Primality::Composite { return c }
+        }
+        let w = from_u128(WINDOW as u128);
+        base = if up { add_vec(&base, &w) } else { sub_vec(&base, &w) };
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Сильный тест Ферма (Миллера-Рабина) для n > 2, взаимно простого с 10
+// n - 1 = d * 2^s, d нечетное
+struct MillerRabin {
+    ring: ModRing,
+    one: Residue,
+    minus_one: Residue,
+    d: Vec<bool>,
+    s: usize
+}
+
+impl MillerRabin {
+    fn new(n: &[u8]) -> Self {
+        let ring = ModRing::new(&BigDecimal { digits: n.to_vec() });
+        let one = ring.one();
+        let minus_one = ring.neg(&one);
+        let mut d = sub_vec(n, &[1]);
+        let mut s = 0;
+        while d[0].is_multiple_of(2) {
+            d = divmod_small(&d, 2).0;
+            s += 1;
+        }
+        Self { ring, one, minus_one, d: to_bits(&d), s }
+    }
+    // true, если n - сильно вероятно простое по основанию a
+    fn test(&self, a: &[u8]) -> bool {
+        let a = self.ring.residue(&BigDecimal { digits: a.to_vec() });
+        let mut x = self.ring.pow_bits(&a, &self.d);
+        if x == self.one || x == self.minus_one { return true }
+        for _ in 1..self.s {
+            x = self.ring.sqr(&x);
+            if x == self.minus_one { return true }
+            if x == self.one { return false }
+        }
+        false
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Сильный тест Люка с параметрами Селфриджа (метод A) для n, взаимно простого с 10
+// D - первое из 5, -7, 9, -11, ... с символом Якоби (D/n) = -1, P = 1, Q = (1 - D) / 4
+fn strong_lucas(n: &[u8]) -> bool {
+    // для точного квадрата подходящего D нет
+    let r = isqrt_vec(n);
+    if sqr(&r) == n { return false }
+    let mut d = 5i64;
+    loop {
+        match jacobi(d, n) {
+            -1 => break,
+            0 if cmp_vec(n, &from_u128(d.unsigned_abs() as u128)) == Ordering::Greater => return false,
+            _ => {}
+        }
+        d = if d > 0 { -d - 2 } else { -d + 2 };
+    }
+    // все вычисления в кольце вычетов по модулю n
+    let ring = ModRing::new(&BigDecimal { digits: n.to_vec() });
+    let signed = |x: i64| {
+        let r = ring.residue(&BigDecimal { digits: from_u128(x.unsigned_abs() as u128) });
+        if x >= 0 { r } else { ring.neg(&r) }
+    };
+    let dm = signed(d);
+    let q = signed((1 - d) / 4);
+    // деление на 2 - умножение на (n + 1) / 2
+    let half = ring.residue(&BigDecimal { digits: divmod_small(&add_vec(n, &[1]), 2).0 });
+    // n + 1 = k * 2^s
+    let mut k = add_vec(n, &[1]);
+    let mut s = 0;
+    while k[0].is_multiple_of(2) {
+        k = divmod_small(&k, 2).0;
+        s += 1;
+    }
+    // U(1) = 1, V(1) = P = 1, Q^1
+    let bits = to_bits(&k);
+    let (mut u, mut v, mut qk) = (ring.one(), ring.one(), q.clone());
+    for &bit in &bits[1..] {
+        // U(2m) = U(m) V(m), V(2m) = V(m)^2 - 2Q^m
+        u = ring.mul(&u, &v);
+        v = ring.sub(&ring.sqr(&v), &ring.add(&qk, &qk));
+        qk = ring.sqr(&qk);
+        if bit {
+            // U(m+1) = (U(m) + V(m)) / 2, V(m+1) = (D U(m) + V(m)) / 2
+            let u1 = ring.mul(&ring.add(&u, &v), &half);
+            let v1 = ring.mul(&ring.add(&ring.mul(&dm, &u), &v), &half);
+            (u, v) = (u1, v1);
+            qk = ring.mul(&qk, &q);
+        }
+    }
+    if u.is_zero() || v.is_zero() { return true }
+    for _ in 1..s {
+        v = ring.sub(&ring.sqr(&v), &ring.add(&qk, &qk));
+        if v.is_zero() { return true }
+        qk = ring.sqr(&qk);
+    }
+    false
+}
+
+// Символ Якоби (a/n) для нечетного n > 0
+fn jacobi(a: i64, n: &[u8]) -> i64 {
+    // n mod 8 определяется тремя младшими цифрами
+    let n8 = to_u128(&n[..n.len().min(3)]) as u64 % 8;
+    // (-1/n) = (-1)^((n-1)/2)
+    let mut res = if a < 0 && n8 % 4 == 3 { -1 } else { 1 };
+    let mut a = a.unsigned_abs();
+    // (2/n) = (-1)^((n^2-1)/8)
+    while a.is_multiple_of(2) && a > 0 {
+        a /= 2;
+        if n8 == 3 || n8 == 5 { res = -res; }
+    }
+    if a == 1 { return res }
+    // закон взаимности: (a/n) = (n/a) * (-1)^((a-1)/2 * (n-1)/2)
+    if a % 4 == 3 && n8 % 4 == 3 { res = -res; }
+    res * jacobi_u64(divmod_small(n, a).1, a)
+}
+
+// Символ Якоби (a/n) для нечетного n > 0
+fn jacobi_u64(mut a: u64, mut n: u64) -> i64 {
+    let mut res = 1;
+    a %= n;
+    while a != 0 {
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 { res = -res; }
+        }
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 { res = -res; }
+        a %= n;
+    }
+    if n == 1 { res } else { 0 }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Числа меньше 2^64
+
+fn is_prime_u64(n: u64) -> Primality {
+    if n < 2 { return Primality::Composite }
+    for p in BASES_64 {
+        if n.is_multiple_of(p) {
+            return if n == p { Primality::Prime } else { Primality::Composite }
+        }
+    }
+    let mut d = n - 1;
+    let s = d.trailing_zeros();
+    d >>= s;
+    let witness = |a: u64| {
+        let mut x = pow_mod_u64(a, d, n);
+        if x == 1 || x == n - 1 { return false }
+        for _ in 1..s {
+            x = mul_mod_u64(x, x, n);
+            if x == n - 1 { return false }
+        }
+        true
+    };
+    if BASES_64.iter().any(|&a| witness(a)) { Primality::Composite } else { Primality::Prime }
+}
+
+pub fn mul_mod_u64(a: u64, b: u64, n: u64) -> u64 {
+    (a as u128 * b as u128 % n as u128) as u64
+}
+
+pub fn pow_mod_u64(mut b: u64, mut e: u64, n: u64) -> u64 {
+    let mut res = 1;
+    b %= n;
+    while e > 0 {
+        if e & 1 == 1 { res = mul_mod_u64(res, b, n); }
+        b = mul_mod_u64(b, b, n);
+        e >>= 1;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    // Число Арно: сильно псевдопростое по всем простым основаниям меньше 307
+    fn arnault() -> BigDecimal {
+        let p1 = big("29674495668685510550154174642905332730771991799853043350995075531276838753171770199594238596428121188033664754218345562493168782883");
+        let p1_minus_one = &p1 - &BigDecimal::one();
+        let mut p2 = &p1_minus_one * &big("313");
+        p2.add_small(1);
+        let mut p3 = &p1_minus_one * &big("353");
+        p3.add_small(1);
+        &(&p1 * &p2) * &p3
+    }
+
+    #[test]
+    fn strong_pseudoprimes_rejected() {
+        // наименьшие сильно псевдопростые по первым 1, 2, ..., 13 простым основаниям
+        let psi = [
+            "2047", "1373653", "25326001", "3215031751", "2152302898747", "3474749660383",
+            "341550071728321", "3825123056546413051", "318665857834031151167461",
+            "3317044064679887385961981"
+        ];
+        for n in psi {
+            let n = big(n);
+            assert_eq!(is_prime(&n), Primality::Composite, "{n}");
+            assert_eq!(baillie_psw(&n), Primality::Composite, "{n}");
+            assert_eq!(miller_rabin(&n, 20), Primality::Composite, "{n}");
+        }
+    }
+
+    #[test]
+    fn arnault_rejected() {
+        let n = arnault();
+        assert_eq!(n.num_digits(), 397);
+        // сильный тест Ферма по основанию 2 оно проходит, отвергает тест Люка
+        assert!(MillerRabin::new(&n.digits).test(&[2]));
+        assert_eq!(is_prime(&n), Primality::Composite);
+    }
+
+    #[test]
+    fn carmichael_rejected() {
+        let small = [
+            561u64, 1105, 1729, 2465, 2821, 6601, 8911, 10585, 15841, 29341, 41041, 46657,
+            52633, 62745, 63973, 75361, 101101, 115921, 126217, 162401, 172081, 188461
+        ];
+        for n in small {
+            assert_eq!(is_prime_u64(n), Primality::Composite, "{n}");
+        }
+        // (6k + 1)(12k + 1)(18k + 1) больше 2^64, делители больше границы пробного деления
+        for n in ["1296198694153288947529", "1296704251525853924209"] {
+            assert_eq!(is_prime(&big(n)), Primality::Composite, "{n}");
+        }
+    }
+
+    #[test]
+    fn u64_matches_sieve() {
+        const N: usize = 1_000_000;
+        let mut sieve = vec![true; N + 1];
+        sieve[0] = false;
+        sieve[1] = false;
+        let mut i = 2;
+        while i * i <= N {
+            if sieve[i] {
+                for j in (i * i..=N).step_by(i) { sieve[j] = false; }
+            }
+            i += 1;
+        }
+        for (n, &p) in sieve.iter().enumerate() {
+            let expected = if p { Primality::Prime } else { Primality::Composite };
+            assert_eq!(is_prime_u64(n as u64), expected, "{n}");
+        }
+    }
+
+    #[test]
+    fn large_primes() {
+        // 2^61 - 1, 2^64 - 59 и 2^89 - 1, 2^127 - 1 (через BPSW)
+        assert_eq!(is_prime(&big("2305843009213693951")), Primality::Prime);
+        assert_eq!(is_prime(&big("18446744073709551557")), Primality::Prime);
+        assert_eq!(is_prime(&big("18446744073709551559")), Primality::Composite);
+        assert_eq!(is_prime(&big("618970019642690137449562111")), Primality::ProbablyPrime);
+        assert_eq!(is_prime(&big("170141183460469231731687303715884105727")), Primality::ProbablyPrime);
+        assert_eq!(miller_rabin(&big("170141183460469231731687303715884105727"), 10), Primality::ProbablyPrime);
+    }
+}
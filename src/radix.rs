@@ -0,0 +1,318 @@
+use std::fmt::{Binary, Formatter, LowerHex, Octal, UpperHex};
+use crate::big::{BigDecimal, ParseBigError};
+use crate::oper::{add_vec, divmod_recip, divmod_small, divmod_vec, from_u128, mul_small, mul_vec, recip_vec, sqr, trim_vec};
+
+// Размер (в цифрах), до которого перевод выполняется делением и умножением на "большую цифру",
+// для больших чисел - делением пополам на степени основания
+const RADIX_LIMIT: usize = 400;
+
+//-------------------------------------------------------------------------------------------------
+// Системы счисления с основаниями от 2 до 36
+// цифры больше 9 - латинские буквы, регистр при разборе не важен
+// (внутренний перевод работает с любым основанием до 256, цифра хранится в u8)
+
+impl BigDecimal {
+    // Разбор записи в системе счисления radix, допускается знак +
+    pub fn from_str_radix(s: &str, radix: u32) -> Result<Self, ParseBigError> {
+        let s = s.strip_prefix('+').unwrap_or(s);
+        check_radix(radix);
+        if s.is_empty() { return Err(ParseBigError) }
+        let r = Radix::new(radix);
+        // цифры, младшая первая
+        let digits = s.bytes().rev()
+            .map(|c| (c as char).to_digit(radix).map(|d| d as u8))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or(ParseBigError)?;
+        if radix == 10 {
+            let mut digits = digits;
+            trim_vec(&mut digits);
+            return Ok(Self { digits })
+        }
+        Ok(Self { digits: r.read(&digits) })
+    }
+
+    // Запись в системе счисления radix (строчные буквы)
+    pub fn to_str_radix(&self, radix: u32) -> String {
+        check_radix(radix);
+        let digits = if radix == 10 { self.digits.clone() } else { Radix::new(radix).write(&self.digits) };
+        digits.iter().rev().map(|&d| std::char::from_digit(d as u32, radix).unwrap()).collect()
+    }
+}
+
+fn check_radix(radix: u32) {
+    if !(2..=36).contains(&radix) { panic!("radix must be in 2..=36"); }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Байтовое представление: запись числа в системе счисления с основанием 256
+
+impl BigDecimal {
+    // Байты, начиная со старшего / младшего; у нуля один нулевой байт
+    pub fn to_bytes_be(&self) -> Vec<u8> {
+        let mut res = self.to_bytes_le();
+        res.reverse();
+        res
+    }
+    pub fn to_bytes_le(&self) -> Vec<u8> {
+        Radix::new(256).write(&self.digits)
+    }
+    // Число по байтам, начиная со старшего / младшего; пустой срез - ноль
+    pub fn from_bytes_be(bytes: &[u8]) -> Self {
+        let le: Vec<u8> = bytes.iter().rev().copied().collect();
+        Self::from_bytes_le(&le)
+    }
+    pub fn from_bytes_le(bytes: &[u8]) -> Self {
+        Self { digits: Radix::new(256).read(bytes) }
+    }
+    // Цифры в основании 2^32 / 2^64, младшая первая; у нуля цифр нет
+    pub fn to_u32_digits(&self) -> Vec<u32> {
+        self.limbs::<4>().into_iter().map(u32::from_le_bytes).collect()
+    }
+    pub fn to_u64_digits(&self) -> Vec<u64> {
+        self.limbs::<8>().into_iter().map(u64::from_le_bytes).collect()
+    }
+
+    // Байты, сгруппированные по N, начиная с младших
+    fn limbs<const N: usize>(&self) -> Vec<[u8; N]> {
+        if self.is_zero() { return Vec::new() }
+        self.to_bytes_le().chunks(N)
+            .map(|c| {
+                let mut limb = [0u8; N];
+                limb[..c.len()].copy_from_slice(c);
+                limb
+            })
+            .collect()
+    }
+}
+
+// Основание и "большая цифра" big = radix^chunk < 10^16,
+// с которой работают быстрые операции с малым множителем
+struct Radix {
+    radix: u64,
+    chunk: usize,
+    big: u64
+}
+
+impl Radix {
+    fn new(radix: u32) -> Self {
+        let radix = radix as u64;
+        let (mut chunk, mut big) = (1, radix);
+        while big * radix < 10_000_000_000_000_000 {
+            big *= radix;
+            chunk += 1;
+        }
+        Self { radix, chunk, big }
+    }
+
+    // Степени big^(2^k), пока done(k, последняя степень) ложно
+    fn powers<F: Fn(usize, &[u8]) -> bool>(&self, done: F) -> Vec<Vec<u8>> {
+        let mut pows = vec![from_u128(self.big as u128)];
+        while !done(pows.len() - 1, &pows[pows.len() - 1]) {
+            let p = sqr(&pows[pows.len() - 1]);
+            pows.push(p);
+        }
+        pows
+    }
+
+    // Десятичные цифры -> цифры в основании radix, младшая первая
+    fn write(&self, x: &[u8]) -> Vec<u8> {
+        // квадрат последней степени больше x
+        let pows = self.powers(|_, p| 2 * p.len() - 1 > x.len());
+        // на каждом уровне много делений на одну и ту же степень - обратные величины считаем один раз
+        let recips: Vec<Option<Vec<u8>>> = pows.iter()
+            .map(|p| if p.len() > RADIX_LIMIT { Some(recip_vec(p, p.len() + 3)) } else { None })
+            .collect();
+        let mut out = Vec::with_capacity(x.len() * 4);
+        self.write_rec(x, pows.len() - 1, &pows, &recips, None, &mut out);
+        // старшие нули от дополнения младших половин до полной ширины
+        trim_vec(&mut out);
+        out
+    }
+
+    // x < pows[k]^2; дописывает ровно width цифр, если ширина задана
+    fn write_rec(&self, x: &[u8], k: usize, pows: &[Vec<u8>], recips: &[Option<Vec<u8>>], width: Option<usize>, out: &mut Vec<u8>) {
+        let start = out.len();
+        if k == 0 || x.len() <= RADIX_LIMIT {
+            let mut x = x.to_vec();
+            while x != [0u8] {
+                let (q, mut r) = divmod_small(&x, self.big);
+                for _ in 0..self.chunk {
+                    out.push((r % self.radix) as u8);
+                    r /= self.radix;
+                }
+                x = q;
+            }
+        } else {
+            // x = hi * big^(2^k) + lo, в младшей половине ровно chunk * 2^k цифр
+            let (hi, lo) = match &recips[k] {
+                Some(y) => divmod_recip(x, &pows[k], y, pows[k].len() + 3),
+                None => divmod_vec(x, &pows[k])
+            };
+            let half = self.chunk << k;
+            self.write_rec(&lo, k - 1, pows, recips, Some(half), out);
+            self.write_rec(&hi, k - 1, pows, recips, width.map(|w| w - half), out);
+            return
+        }
+        if let Some(w) = width { out.resize(start + w, 0); }
+    }
+
+    // Цифры в основании radix (младшая первая) -> десятичные цифры
+    fn read(&self, d: &[u8]) -> Vec<u8> {
+        // в big^(2^(k+1)) не меньше цифр, чем в d
+        let pows = self.powers(|k, _| self.chunk << (k + 1) >= d.len());
+        let mut res = self.read_rec(d, pows.len() - 1, &pows);
+        trim_vec(&mut res);
+        res
+    }
+
+    fn read_rec(&self, d: &[u8], k: usize, pows: &[Vec<u8>]) -> Vec<u8> {
+        let half = self.chunk << k;
+        if k > 0 && d.len() > RADIX_LIMIT {
+            if d.len() <= half { return self.read_rec(d, k - 1, pows) }
+            // d = hi * big^(2^k) + lo
+            let lo = self.read_rec(&d[..half], k - 1, pows);
+            let hi = self.read_rec(&d[half..], k - 1, pows);
+            return add_vec(&mul_vec(&hi, &pows[k]), &lo)
+        }
+        // схема Горнера по "большим цифрам", начиная со старших
+        let mut acc = vec![0u8];
+        for c in d.rchunks(self.chunk) {
+            let v = c.iter().rev().fold(0u64, |v, &x| v * self.radix + x as u64);
+            let scale = if c.len() == self.chunk { self.big } else { self.radix.pow(c.len() as u32) };
+            acc = add_vec(&mul_small(&acc, scale), &from_u128(v as u128));
+        }
+        acc
+    }
+}
+
+//-------------------------------------------------------------------------------------------------
+// Форматирование {:x}, {:X}, {:b}, {:o}; с флагом # добавляется префикс 0x, 0b, 0o
+
+impl LowerHex for BigDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad_integral(true, "0x", &self.to_str_radix(16))
+    }
+}
+
+impl UpperHex for BigDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad_integral(true, "0x", &self.to_str_radix(16).to_uppercase())
+    }
+}
+
+impl Binary for BigDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad_integral(true, "0b", &self.to_str_radix(2))
+    }
+}
+
+impl Octal for BigDecimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.pad_integral(true, "0o", &self.to_str_radix(8))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    // длины около границ RADIX_LIMIT и ее кратных, где включается деление пополам
+    // и деление через обратную величину
+    const LENS: [usize; 12] = [1, 2, 19, 399, 400, 401, 799, 800, 801, 1600, 3201, 6400];
+
+    #[test]
+    fn known_values() {
+        let x = big("515377520732011331036461129765621272702107522001");
+        assert_eq!(x.to_str_radix(16), "5a4653ca673768565b41f775d6947d55cf3813d1");
+        assert_eq!(x.to_str_radix(8), "55106247451471566412626640767353532243725271716011721");
+        assert_eq!(x.to_str_radix(36), "ajmfwc7pep3zss2fwkm9zm45pd86w29");
+        assert_eq!(BigDecimal::from_str_radix("AJMFWC7PEP3ZSS2FWKM9ZM45PD86W29", 36), Ok(x.clone()));
+        assert_eq!(BigDecimal::from_str_radix("+5a4653ca673768565b41f775d6947d55cf3813d1", 16), Ok(x.clone()));
+        assert_eq!(format!("{x:#x}"), "0x5a4653ca673768565b41f775d6947d55cf3813d1");
+        assert_eq!(format!("{:X}", big("255")), "FF");
+        assert_eq!(format!("{:#b}", big("5")), "0b101");
+        assert_eq!(format!("{:o}", big("8")), "10");
+        assert_eq!(BigDecimal::zero().to_str_radix(2), "0");
+        assert_eq!(BigDecimal::from_str_radix("000", 7), Ok(BigDecimal::zero()));
+        assert_eq!(BigDecimal::from_str_radix("", 16), Err(ParseBigError));
+        assert_eq!(BigDecimal::from_str_radix("12g", 16), Err(ParseBigError));
+        assert_eq!(BigDecimal::from_str_radix("-1", 10), Err(ParseBigError));
+    }
+
+    #[test]
+    fn powers_of_radix() {
+        // radix^k - 1 записывается k старшими цифрами, radix^k - единицей и k нулями
+        for (radix, top) in [(2u32, '1'), (16, 'f'), (36, 'z')] {
+            let mut x = BigDecimal::one();
+            for k in 1..=3000usize {
+                x = BigDecimal { digits: mul_small(&x.digits, radix as u64) };
+                if k % 500 == 0 || k == 1 {
+                    let s = x.to_str_radix(radix);
+                    assert_eq!(s, format!("1{}", "0".repeat(k)), "{radix}^{k}");
+                    let y = &x - &BigDecimal::one();
+                    assert_eq!(y.to_str_radix(radix), top.to_string().repeat(k), "{radix}^{k} - 1");
+                    assert_eq!(BigDecimal::from_str_radix(&s, radix), Ok(x.clone()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn decimal_round_trip() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for n in LENS {
+            let x = BigDecimal::random_digits(n, &mut rng);
+            for radix in [2, 3, 7, 16, 36] {
+                let s = x.to_str_radix(radix);
+                assert_eq!(BigDecimal::from_str_radix(&s, radix), Ok(x.clone()), "{n} digits, radix {radix}");
+            }
+            assert_eq!(x.to_str_radix(10), x.to_string());
+            assert_eq!(BigDecimal::from_bytes_le(&x.to_bytes_le()), x, "{n} digits");
+            assert_eq!(BigDecimal::from_bytes_be(&x.to_bytes_be()), x, "{n} digits");
+        }
+    }
+
+    #[test]
+    fn radix_round_trip() {
+        // запись в основании radix длины n, старшая цифра не 0
+        let mut rng = StdRng::seed_from_u64(2);
+        for n in LENS {
+            for radix in [2u32, 16, 36] {
+                let s: String = (0..n)
+                    .map(|i| std::char::from_digit(rng.gen_range(if i == 0 { 1 } else { 0 }..radix), radix).unwrap())
+                    .collect();
+                let x = BigDecimal::from_str_radix(&s, radix).unwrap();
+                assert_eq!(x.to_str_radix(radix), s, "{n} digits, radix {radix}");
+            }
+            let bytes: Vec<u8> = (0..n).map(|i| if i + 1 == n { rng.gen_range(1..=255) } else { rng.r#gen() }).collect();
+            assert_eq!(BigDecimal::from_bytes_le(&bytes).to_bytes_le(), bytes, "{n} bytes");
+        }
+    }
+
+    #[test]
+    fn bytes_and_limbs() {
+        // 2^64 + 5
+        let x = big("18446744073709551621");
+        assert_eq!(x.to_bytes_le(), vec![5, 0, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(x.to_bytes_be(), vec![1, 0, 0, 0, 0, 0, 0, 0, 5]);
+        assert_eq!(x.to_u64_digits(), vec![5, 1]);
+        assert_eq!(x.to_u32_digits(), vec![5, 0, 1]);
+        assert_eq!(BigDecimal::from_bytes_be(&[0, 0, 1, 0]), big("256"));
+        assert_eq!(BigDecimal::from_bytes_le(&[]), BigDecimal::zero());
+        assert_eq!(BigDecimal::zero().to_bytes_le(), vec![0]);
+        assert_eq!(BigDecimal::zero().to_u64_digits(), Vec::<u64>::new());
+        let x = big("340282366920938463463374607431768211455");
+        assert_eq!(x.to_u64_digits(), vec![u64::MAX, u64::MAX]);
+        assert_eq!(x.to_bytes_le(), vec![255; 16]);
+    }
+
+    #[test]
+    #[should_panic(expected = "radix must be in 2..=36")]
+    fn bad_radix_panics() {
+        BigDecimal::one().to_str_radix(37);
+    }
+}
@@ -0,0 +1,180 @@
+use std::ops::Range;
+use rand::distributions::uniform::{SampleBorrow, SampleUniform, UniformSampler};
+use rand::distributions::Distribution;
+use rand::Rng;
+use crate::big::BigDecimal;
+use crate::oper::{cmp_vec, trim_vec};
+
+//-------------------------------------------------------------------------------------------------
+// Случайные большие числа
+// распределения для rand: RandomDigits, RandomBits и равномерное на отрезке,
+// так что работают rng.sample(...), rng.gen_range(a..b) и Uniform::new(a, b)
+
+// Наибольшая степень 10, помещающаяся в u64: цифры генерируются пачками по 19
+const CHUNK: usize = 19;
+
+impl BigDecimal {
+    // Равномерно распределенное n-значное число (старшая цифра не 0)
+    pub fn random_digits<R: Rng + ?Sized>(n: usize, rng: &mut R) -> Self {
+        RandomDigits(n).sample(rng)
+    }
+    // Равномерно распределенное число из [0, bound)
+    pub fn random_below<R: Rng + ?Sized>(bound: &BigDecimal, rng: &mut R) -> Self {
+        if bound.is_zero() { panic!("empty range"); }
+        let n = bound.digits.len();
+        let top = bound.digits[n - 1];
+        // старшая цифра не больше старшей цифры bound, поэтому отбрасывается меньше половины чисел
+        loop {
+            let mut digits = vec![0u8; n];
+            fill_digits(&mut digits[..n - 1], rng);
+            digits[n - 1] = rng.gen_range(0..=top);
+            trim_vec(&mut digits);
+            if cmp_vec(&digits, &bound.digits).is_lt() { return Self { digits } }
+        }
+    }
+    // Равномерно распределенное число из [lo, hi)
+    pub fn random_range<R: Rng + ?Sized>(range: Range<BigDecimal>, rng: &mut R) -> Self {
+        rng.gen_range(range)
+    }
+    // Равномерно распределенное число из [0, 2^k)
+    pub fn random_bits<R: Rng + ?Sized>(k: u64, rng: &mut R) -> Self {
+        RandomBits(k).sample(rng)
+    }
+}
+
+// Заполнить срез случайными цифрами
+fn fill_digits<R: Rng + ?Sized>(out: &mut [u8], rng: &mut R) {
+    for chunk in out.chunks_mut(CHUNK) {
+        // младшие цифры равномерного на [0, 10^19) числа распределены равномерно
+        let mut v = rng.gen_range(0..10u64.pow(CHUNK as u32));
+        for d in chunk {
+            *d = (v % 10) as u8;
+            v /= 10;
+        }
+    }
+}
+
+// n-значные числа
+#[derive(Clone, Copy, Debug)]
+pub struct RandomDigits(pub usize);
+
+impl Distribution<BigDecimal> for RandomDigits {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> BigDecimal {
+        let n = self.0;
+        if n == 0 { panic!("number must have at least one digit"); }
+        let mut digits = vec![0u8; n];
+        fill_digits(&mut digits[..n - 1], rng);
+        digits[n - 1] = rng.gen_range(1..=9);
+        BigDecimal { digits }
+    }
+}
+
+// Числа из k случайных битов
+#[derive(Clone, Copy, Debug)]
+pub struct RandomBits(pub u64);
+
+impl Distribution<BigDecimal> for RandomBits {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> BigDecimal {
+        let k = self.0;
+        let mut bytes = vec![0u8; k.div_ceil(8) as usize];
+        rng.fill(&mut bytes[..]);
+        // лишние старшие биты последнего байта
+        if !k.is_multiple_of(8) { *bytes.last_mut().unwrap() &= (1u8 << (k % 8)) - 1; }
+        BigDecimal::from_bytes_le(&bytes)
+    }
+}
+
+// Равномерное распределение на [low, low + len)
+#[derive(Clone, Debug)]
+pub struct UniformBigDecimal {
+    low: BigDecimal,
+    len: BigDecimal
+}
+
+impl UniformSampler for UniformBigDecimal {
+    type X = BigDecimal;
+    fn new<B1, B2>(low: B1, high: B2) -> Self
+    where B1: SampleBorrow<Self::X> + Sized, B2: SampleBorrow<Self::X> + Sized {
+        let (low, high) = (low.borrow(), high.borrow());
+        if low >= high { panic!("empty range"); }
+        Self { low: low.clone(), len: high - low }
+    }
+    fn new_inclusive<B1, B2>(low: B1, high: B2) -> Self
+    where B1: SampleBorrow<Self::X> + Sized, B2: SampleBorrow<Self::X> + Sized {
+        let (low, high) = (low.borrow(), high.borrow());
+        if low > high { panic!("empty range"); }
+        let mut len = high - low;
+        len.add_small(1);
+        Self { low: low.clone(), len }
+    }
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Self::X {
+        &self.low + BigDecimal::random_below(&self.len, rng)
+    }
+}
+
+impl SampleUniform for BigDecimal {
+    type Sampler = UniformBigDecimal;
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use super::*;
+
+    fn big(s: &str) -> BigDecimal { BigDecimal::from(s) }
+
+    #[test]
+    fn below_and_range_in_bounds() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for bound in ["1", "2", "10", "99", "100", "1000000000000000000000", "987654321987654321987654321"] {
+            let bound = big(bound);
+            for _ in 0..500 {
+                assert!(BigDecimal::random_below(&bound, &mut rng) < bound);
+            }
+        }
+        // все значения малого отрезка встречаются
+        let mut seen = [false; 10];
+        for _ in 0..1000 {
+            let x = BigDecimal::random_below(&big("10"), &mut rng);
+            seen[x.digit(0) as usize] = true;
+        }
+        assert!(seen.iter().all(|&x| x));
+        let (lo, hi) = (big("123456789012345678901234567890"), big("123456789012345678901234568000"));
+        for _ in 0..500 {
+            let x = BigDecimal::random_range(lo.clone()..hi.clone(), &mut rng);
+            assert!(lo <= x && x < hi);
+            let x = rng.gen_range(lo.clone()..=hi.clone());
+            assert!(lo <= x && x <= hi);
+        }
+        assert_eq!(rng.gen_range(lo.clone()..=lo.clone()), lo);
+    }
+
+    #[test]
+    fn digits_count() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for n in [1, 2, 18, 19, 20, 38, 100, 1001] {
+            for _ in 0..50 {
+                assert_eq!(BigDecimal::random_digits(n, &mut rng).num_digits(), n);
+            }
+        }
+    }
+
+    #[test]
+    fn bits_below_power_of_two() {
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(BigDecimal::random_bits(0, &mut rng).is_zero());
+        let mut power = BigDecimal::one();
+        for k in 1..=200u64 {
+            power = &power + &power;
+            let mut max = BigDecimal::zero();
+            for _ in 0..20 {
+                let x = BigDecimal::random_bits(k, &mut rng);
+                assert!(x < power, "k = {k}");
+                max = max.max(x);
+            }
+            // старший бит хотя бы раз из 20 выпадает
+            assert!(max >= &power / &big("2"), "k = {k}");
+        }
+    }
+}
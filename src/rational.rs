@@ -8,6 +8,7 @@ enum Sign {
     Null
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Ratio {
     numerator: i64,
     denominator: i64,
@@ -17,25 +18,28 @@ impl Ratio {
     // new rational number
     pub fn new(n: i64, d: i64) -> Self {
         if d != 0 {
-            let g = if n != 0 {
-                gcd(n.abs() as u64, d.abs() as u64) as i64
-            } else {
-                1
-            };
+            // gcd(0, d) = |d|, so zero is always 0/1
+            let g = gcd(n.unsigned_abs(), d.unsigned_abs()) as i64;
+            // keep the sign in the numerator
+            let s = if d < 0 { -1 } else { 1 };
             Ratio {
-                numerator: n / g,
-                denominator: d / g
+                numerator: s * n / g,
+                denominator: s * d / g
             }
         } else {
             panic!("division by zero");
         }
     }
+    // numerator
+    pub fn numer(&self) -> i64 { self.numerator }
+    // denominator
+    pub fn denom(&self) -> i64 { self.denominator }
     // new reciprocal number
     pub fn reciproc(n: i64) -> Self {
         if n != 0 {
             Ratio {
-                numerator: 1i64,
-                denominator: n
+                numerator: n.signum(),
+                denominator: n.abs()
             }
         } else {
             panic!("division by zero");
@@ -82,6 +86,7 @@ impl From<i64> for Ratio {
 
 fn gcd(x: u64, y: u64) -> u64 {
     if y == 0 { return x }
+    if x == 0 { return y }
     if x >= y {
         gcd(y, x % y)
     } else {
@@ -155,3 +160,232 @@ impl DivAssign for Ratio {
         self.numerator = x.numerator;
     }
 }
+
+//------------------------------------------------------------------------------------------------
+// Continued fractions
+impl Ratio {
+    // continued fraction expansion [a0; a1, a2, ...]
+    // a0 = floor(x), the remaining terms are positive
+    // the denominator is positive, so every term fits in i64
+    pub fn continued_fraction(&self) -> Vec<i64> {
+        let (mut n, mut d) = (self.numerator, self.denominator);
+        let mut res = Vec::new();
+        while d != 0 {
+            res.push(n.div_euclid(d));
+            (n, d) = (d, n.rem_euclid(d));
+        }
+        res
+    }
+    // rational number from its continued fraction,
+    // None if there are no terms or the result does not fit in i64
+    pub fn from_continued_fraction(terms: &[i64]) -> Option<Self> {
+        if terms.iter().skip(1).any(|&a| a <= 0) {
+            panic!("continued fraction terms after the first must be positive");
+        }
+        let mut convergents = Convergents::new(terms.to_vec());
+        let res = convergents.by_ref().last()?;
+        // the iterator stops early on overflow
+        if convergents.overflow { None } else { Some(res) }
+    }
+    // sequence of convergents
+    pub fn convergents(&self) -> Convergents {
+        Convergents::new(self.continued_fraction())
+    }
+    // closest ratio with denominator at most max_denominator
+    pub fn limit_denominator(&self, max_denominator: i64) -> Self {
+        if max_denominator < 1 { panic!("max_denominator must be positive"); }
+        if self.denominator <= max_denominator { return *self }
+        let (n, d) = (i128::from(self.numerator), i128::from(self.denominator));
+        // |n/d - p/q| < |n/d - r/s| <=> |n*q - d*p| * s < |n*s - d*r| * q
+        best_approximation(
+            self.continued_fraction().into_iter().map(i128::from),
+            max_denominator,
+            |(p, q), (r, s)| (n * q - d * p).abs() * s < (n * s - d * r).abs() * q
+        )
+    }
+    // best rational approximation of a float
+    // with denominator at most max_denominator and numerator fitting in i64
+    pub fn approximate(x: f64, max_denominator: i64) -> Self {
+        if !x.is_finite() || x.abs() >= i64::MAX as f64 {
+            panic!("{x} cannot be approximated by a ratio");
+        }
+        if max_denominator < 1 { panic!("max_denominator must be positive"); }
+        best_approximation(
+            FloatTerms(Some(x)),
+            max_denominator,
+            |(p, q), (r, s)| (x - p as f64 / q as f64).abs() < (x - r as f64 / s as f64).abs()
+        )
+    }
+}
+
+// Iterator over the convergents of a continued fraction
+// p(k) = a(k) * p(k-1) + p(k-2), q(k) = a(k) * q(k-1) + q(k-2)
+// stops at the first convergent that does not fit in i64
+pub struct Convergents {
+    terms: std::vec::IntoIter<i64>,
+    p: (i128, i128),
+    q: (i128, i128),
+    overflow: bool
+}
+
+impl Convergents {
+    pub fn new(terms: Vec<i64>) -> Self {
+        Self { terms: terms.into_iter(), p: (0, 1), q: (1, 0), overflow: false }
+    }
+}
+
+impl Iterator for Convergents {
+    type Item = Ratio;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.overflow { return None }
+        // previous convergents fit in i64, so the i128 products do not overflow
+        let a = i128::from(self.terms.next()?);
+        let p = a * self.p.1 + self.p.0;
+        let q = a * self.q.1 + self.q.0;
+        match (i64::try_from(p), i64::try_from(q)) {
+            (Ok(n), Ok(d)) if d != 0 => {
+                self.p = (self.p.1, p);
+                self.q = (self.q.1, q);
+                Some(Ratio::new(n, d))
+            },
+            _ => {
+                self.overflow = true;
+                None
+            }
+        }
+    }
+}
+
+// Continued fraction terms of a float
+struct FloatTerms(Option<f64>);
+
+impl Iterator for FloatTerms {
+    type Item = i128;
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.0?;
+        let a = x.floor();
+        let f = x - a;
+        self.0 = if f > 0. { Some(1. / f) } else { None };
+        Some(a as i128)
+    }
+}
+
+// Stern-Brocot descent accelerated by the continued fraction:
+// walk the convergents while the denominator is at most max_den and the numerator fits in i64,
+// then pick the closer of the last convergent and the best semiconvergent within the same bounds.
+// closer(x, y) is true if x is closer to the target than y
+// (the first term must fit in i64, the rest must be positive)
+fn best_approximation<I, F>(terms: I, max_den: i64, closer: F) -> Ratio
+where
+    I: Iterator<Item = i128>,
+    F: Fn((i128, i128), (i128, i128)) -> bool
+{
+    let (max_num, max_den) = (i128::from(i64::MAX), i128::from(max_den));
+    let step = |a: i128, x1: i128, x0: i128| a.checked_mul(x1).and_then(|x| x.checked_add(x0));
+    let (mut p0, mut q0, mut p1, mut q1) = (0i128, 1i128, 1i128, 0i128);
+    for a in terms {
+        let p2 = step(a, p1, p0).filter(|p| p.abs() <= max_num);
+        let q2 = step(a, q1, q0).filter(|&q| q <= max_den);
+        if let (Some(p2), Some(q2)) = (p2, q2) {
+            (p0, q0, p1, q1) = (p1, q1, p2, q2);
+            continue
+        }
+        // p0 and p1 have the same sign, so |p0 + k * p1| = |p0| + k * |p1|
+        let mut k = (max_den - q0) / q1;
+        if p1 != 0 { k = k.min((max_num - p0.abs()) / p1.abs()); }
+        let semi = (p0 + k * p1, q0 + k * q1);
+        if closer(semi, (p1, q1)) { (p1, q1) = semi; }
+        break
+    }
+    let fit = |x: i128| i64::try_from(x).expect("bounded by max_num and max_den");
+    Ratio::new(fit(p1), fit(q1))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{PI, SQRT_2};
+    use super::*;
+
+    #[test]
+    fn zero_is_normalized() {
+        assert_eq!(Ratio::new(0, 5), Ratio::new(0, 1));
+        assert_eq!(Ratio::new(0, -3), Ratio::from(0));
+        assert_eq!(Ratio::new(0, 7).denom(), 1);
+        assert_eq!(Ratio::new(3, -6), Ratio::new(-1, 2));
+        assert_eq!(Ratio::new(2, 3) - Ratio::new(4, 6), Ratio::from(0));
+    }
+
+    #[test]
+    fn continued_fraction() {
+        assert_eq!(Ratio::new(415, 93).continued_fraction(), vec![4, 2, 6, 7]);
+        assert_eq!(Ratio::new(-7, 3).continued_fraction(), vec![-3, 1, 2]);
+        assert_eq!(Ratio::from(5).continued_fraction(), vec![5]);
+        assert_eq!(Ratio::from(-5).continued_fraction(), vec![-5]);
+        assert_eq!(Ratio::new(0, 4).continued_fraction(), vec![0]);
+        assert_eq!(Ratio::new(i64::MIN, 1).continued_fraction(), vec![i64::MIN]);
+        assert_eq!(Ratio::new(1, i64::MAX).continued_fraction(), vec![0, i64::MAX]);
+        for r in [Ratio::new(415, 93), Ratio::new(-7, 3), Ratio::from(0), Ratio::new(i64::MAX, i64::MAX - 1)] {
+            assert_eq!(Ratio::from_continued_fraction(&r.continued_fraction()), Some(r));
+        }
+    }
+
+    #[test]
+    fn from_continued_fraction_edge_cases() {
+        assert_eq!(Ratio::from_continued_fraction(&[]), None);
+        assert_eq!(Ratio::from_continued_fraction(&[-3, 1, 2]), Some(Ratio::new(-7, 3)));
+        // numerator or denominator out of i64
+        assert_eq!(Ratio::from_continued_fraction(&[i64::MAX, 1]), None);
+        assert_eq!(Ratio::from_continued_fraction(&[0, i64::MAX, 2]), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be positive")]
+    fn from_continued_fraction_rejects_zero_term() {
+        Ratio::from_continued_fraction(&[1, 0, 2]);
+    }
+
+    #[test]
+    fn convergents() {
+        let c: Vec<Ratio> = Ratio::new(415, 93).convergents().collect();
+        assert_eq!(c, vec![Ratio::from(4), Ratio::new(9, 2), Ratio::new(58, 13), Ratio::new(415, 93)]);
+        let c: Vec<Ratio> = Ratio::new(0, 9).convergents().collect();
+        assert_eq!(c, vec![Ratio::from(0)]);
+        // stops at the first convergent that does not fit
+        let c: Vec<Ratio> = Convergents::new(vec![1, i64::MAX, 2]).collect();
+        assert_eq!(c, vec![Ratio::from(1)]);
+    }
+
+    #[test]
+    fn limit_denominator() {
+        assert_eq!(Ratio::new(415, 93).limit_denominator(20), Ratio::new(58, 13));
+        assert_eq!(Ratio::new(-415, 93).limit_denominator(20), Ratio::new(-58, 13));
+        assert_eq!(Ratio::new(415, 93).limit_denominator(100), Ratio::new(415, 93));
+        assert_eq!(Ratio::from(-5).limit_denominator(1), Ratio::from(-5));
+        // large numerators near the i64 limit
+        assert_eq!(Ratio::new(i64::MAX, 3).limit_denominator(2), Ratio::new(6148914691236517205, 2));
+        assert_eq!(Ratio::new(i64::MAX, 2).limit_denominator(1), Ratio::from(4611686018427387903));
+    }
+
+    #[test]
+    fn approximate() {
+        assert_eq!(Ratio::approximate(PI, 1000), Ratio::new(355, 113));
+        assert_eq!(Ratio::approximate(PI, 100), Ratio::new(311, 99));
+        assert_eq!(Ratio::approximate(PI, 10), Ratio::new(22, 7));
+        assert_eq!(Ratio::approximate(-PI, 1000), Ratio::new(-355, 113));
+        assert_eq!(Ratio::approximate(SQRT_2, 1_000_000), Ratio::new(665857, 470832));
+        assert_eq!(Ratio::approximate(3.0, 10), Ratio::from(3));
+        assert_eq!(Ratio::approximate(-0.25, 10), Ratio::new(-1, 4));
+        assert_eq!(Ratio::approximate(0.0, 10), Ratio::from(0));
+        assert_eq!(Ratio::approximate(1e-30, 1000), Ratio::from(0));
+        assert_eq!(Ratio::approximate(-9.0e18, 10), Ratio::from(-9_000_000_000_000_000_000));
+        // numerators stay in i64 even with a huge denominator limit
+        let r = Ratio::approximate(9.0e18 + 1024.0, i64::MAX);
+        assert_eq!(r, Ratio::from(9_000_000_000_000_001_024));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be approximated")]
+    fn approximate_rejects_out_of_range() {
+        Ratio::approximate(1e19, 10);
+    }
+}
@@ -0,0 +1,226 @@
+// Размер сегмента решета (число нечетных чисел в сегменте)
+const SEGMENT: u64 = 1 << 18;
+
+// До этой границы простые для просеивания считаются простым решетом, дальше - сегментами
+const BASE_LIMIT: u64 = 1 << 24;
+
+//-------------------------------------------------------------------------------------------------
+// Сегментированное решето Эратосфена
+// просеиваются только нечетные числа, сегментами по SEGMENT чисел,
+// так что память не зависит от границы, кроме простых до ее квадратного корня
+
+// Простые числа не больше n
+pub fn primes_upto(n: u64) -> Vec<u64> {
+    primes_between(2, n)
+}
+
+// Простые числа p, low <= p <= high
+pub fn primes_between(low: u64, high: u64) -> Vec<u64> {
+    Primes::new(low, high).collect()
+}
+
+// Все простые числа по порядку: 2, 3, 5, 7, 11, ...
+pub fn primes() -> Primes {
+    Primes::new(2, u64::MAX)
+}
+
+// Итератор по простым числам отрезка, сегменты просеиваются по мере надобности
+pub struct Primes {
+    buf: Vec<u64>,
+    pos: usize,
+    // начало следующего сегмента (нечетное), None - отрезок исчерпан
+    next: Option<u64>,
+    high: u64,
+    // простые для просеивания: все простые не больше base_limit (меньше 2^32, поэтому u32),
+    // хранятся все время работы итератора и только дополняются
+    base: Vec<u32>,
+    base_limit: u64
+}
+
+impl Primes {
+    fn new(low: u64, high: u64) -> Self {
+        let buf = if low <= 2 && high >= 2 { vec![2] } else { Vec::new() };
+        // первое нечетное, не меньшее max(low, 3)
+        let start = low.max(3) | 1;
+        let next = if start <= high { Some(start) } else { None };
+        Self { buf, pos: 0, next, high, base: Vec::new(), base_limit: 1 }
+    }
+
+    // просеять очередной сегмент [lo, hi]
+    fn fill(&mut self, lo: u64) {
+        let hi = lo.saturating_add(2 * (SEGMENT - 1)).min(self.high);
+        let root = hi.isqrt();
+        self.buf.clear();
+        self.pos = 0;
+        if self.base_limit < root {
+            // запас, чтобы не дополнять базу на каждом сегменте
+            self.grow_base(root.saturating_mul(2).min(u32::MAX as u64));
+        }
+        sieve_segment(lo, hi, self.base.iter().skip(1).map(|&p| p as u64), &mut self.buf);
+        self.next = hi.checked_add(2).filter(|&x| x <= self.high);
+    }
+
+    // дополнить базу всеми простыми до limit: сначала простое решето до BASE_LIMIT,
+    // дальше сегменты, просеиваемые уже найденными простыми (их хватает до base_limit^2)
+    fn grow_base(&mut self, limit: u64) {
+        if self.base_limit < BASE_LIMIT {
+            self.base_limit = limit.min(BASE_LIMIT);
+            self.base = small_sieve(self.base_limit).into_iter().map(|p| p as u32).collect();
+        }
+        let mut out = Vec::new();
+        while self.base_limit < limit {
+            let lo = (self.base_limit + 1) | 1;
+            let hi = limit.min(self.base_limit.saturating_mul(self.base_limit)).min(lo + 2 * (SEGMENT - 1));
+            out.clear();
+            sieve_segment(lo, hi, self.base.iter().skip(1).map(|&p| p as u64), &mut out);
+            self.base.extend(out.iter().map(|&p| p as u32));
+            self.base_limit = hi;
+        }
+    }
+}
+
+impl Iterator for Primes {
+    type Item = u64;
+    fn next(&mut self) -> Option<u64> {
+        while self.pos == self.buf.len() {
+            let lo = self.next?;
+            self.fill(lo);
+        }
+        self.pos += 1;
+        Some(self.buf[self.pos - 1])
+    }
+}
+
+// Простые нечетного отрезка [lo, hi], lo >= 3 нечетное
+// base перечисляет по возрастанию все нечетные простые до sqrt(hi)
+fn sieve_segment<I: IntoIterator<Item = u64>>(lo: u64, hi: u64, base: I, out: &mut Vec<u64>) {
+    if lo > hi { return }
+    // sieve[i] - составное ли lo + 2i
+    let size = ((hi - lo) / 2 + 1) as usize;
+    let mut sieve = vec![false; size];
+    for p in base {
+        if p * p > hi { break }
+        // первое нечетное кратное p, не меньшее max(lo, p^2)
+        // (в u128, чтобы не переполниться у верхней границы u64)
+        let mut m = lo.div_ceil(p) as u128 * p as u128;
+        if m.is_multiple_of(2) { m += p as u128; }
+        let start = m.max((p * p) as u128);
+        if start > hi as u128 { continue }
+        let start = start as u64;
+        for j in (((start - lo) / 2) as usize..size).step_by(p as usize) { sieve[j] = true; }
+    }
+    out.extend((0..size).filter(|&i| !sieve[i]).map(|i| lo + 2 * i as u64));
+}
+
+// Простые числа не больше n (простое решето по нечетным)
+fn small_sieve(n: u64) -> Vec<u64> {
+    if n < 2 { return Vec::new() }
+    // sieve[i] - составное ли 2i + 1
+    let size = ((n - 1) / 2 + 1) as usize;
+    let mut sieve = vec![false; size];
+    let mut i = 1;
+    while (2 * i + 1) * (2 * i + 1) <= n as usize {
+        if !sieve[i] {
+            let p = 2 * i + 1;
+            for j in (p * p / 2..size).step_by(p) { sieve[j] = true; }
+        }
+        i += 1;
+    }
+    let mut res = vec![2];
+    res.extend((1..size).filter(|&i| !sieve[i]).map(|i| 2 * i as u64 + 1));
+    res
+}
+
+//-------------------------------------------------------------------------------------------------
+// Число простых, не больших n (метод Люси Хеджхог, O(n^(3/4)) операций)
+// S(v) - число чисел 2..v, не вычеркнутых простыми меньше p; после всех p <= sqrt(n)
+// S(v) = pi(v); нужны только значения v вида n / i
+pub fn prime_pi(n: u64) -> u64 {
+    if n < 2 { return 0 }
+    let r = n.isqrt();
+    // small[v] = S(v) для v <= r, large[i] = S(n / i) для i <= r
+    let mut small: Vec<u64> = (0..=r).map(|v| v.saturating_sub(1)).collect();
+    let mut large: Vec<u64> = (0..=r).map(|i| n.checked_div(i).map_or(0, |q| q - 1)).collect();
+    for p in 2..=r {
+        if small[p as usize] == small[p as usize - 1] { continue }
+        // p простое, вычеркиваем числа с наименьшим простым делителем p
+        let cnt = small[p as usize - 1];
+        let p2 = p * p;
+        for i in 1..=r.min(n / p2) {
+            let d = i * p;
+            let s = if d <= r { large[d as usize] } else { small[(n / d) as usize] };
+            large[i as usize] -= s - cnt;
+        }
+        for v in (p2..=r).rev() {
+            small[v as usize] -= small[(v / p) as usize] - cnt;
+        }
+    }
+    large[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // проверка пробным делением
+    fn is_prime_naive(n: u64) -> bool {
+        n >= 2 && (2..=n.isqrt()).all(|d| !n.is_multiple_of(d))
+    }
+
+    #[test]
+    fn prime_pi_powers_of_ten() {
+        let pi = [0, 4, 25, 168, 1229, 9592, 78498, 664579, 5761455, 50847534, 455052511];
+        let mut n = 1u64;
+        for (k, &x) in pi.iter().enumerate() {
+            assert_eq!(prime_pi(n), x, "10^{k}");
+            n *= 10;
+        }
+        assert_eq!(prime_pi(0), 0);
+        assert_eq!(prime_pi(2), 1);
+        assert_eq!(prime_pi(3), 2);
+    }
+
+    #[test]
+    fn prime_pi_matches_sieve() {
+        let primes = primes_upto(100_000);
+        for n in (0..100_000u64).step_by(997).chain([99_999, 100_000]) {
+            assert_eq!(prime_pi(n), primes.partition_point(|&p| p <= n) as u64, "n = {n}");
+        }
+    }
+
+    #[test]
+    fn sieve_small() {
+        assert_eq!(primes_upto(30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert_eq!(primes_upto(1), Vec::<u64>::new());
+        assert_eq!(primes_upto(2), vec![2]);
+        assert_eq!(primes_between(20, 23), vec![23]);
+        assert_eq!(primes_between(24, 28), Vec::<u64>::new());
+        assert_eq!(primes_between(30, 20), Vec::<u64>::new());
+        let naive: Vec<u64> = (0..10_000).filter(|&n| is_prime_naive(n)).collect();
+        assert_eq!(primes_upto(10_000), naive);
+        assert_eq!(small_sieve(10_000), naive);
+    }
+
+    #[test]
+    fn segment_boundaries() {
+        // первый сегмент кончается на 3 + 2 (SEGMENT - 1) = 524289
+        let end = 3 + 2 * (SEGMENT - 1);
+        assert_eq!(primes_between(end - 20, end + 19), vec![524269, 524287]);
+        let lo = 5 * SEGMENT;
+        let naive: Vec<u64> = (lo..lo + 6 * SEGMENT).filter(|&n| is_prime_naive(n)).collect();
+        assert_eq!(primes_between(lo, lo + 6 * SEGMENT - 1), naive);
+        // итератор без верхней границы
+        assert_eq!(primes().nth(9999), Some(104729));
+        assert_eq!(primes().take(5).collect::<Vec<_>>(), vec![2, 3, 5, 7, 11]);
+    }
+
+    #[test]
+    fn beyond_base_limit() {
+        // для просеивания нужны простые больше BASE_LIMIT
+        let lo = 1u64 << 50;
+        let p = primes_between(lo, lo + 1000);
+        assert_eq!(p.len(), 34);
+        assert_eq!(p[0], 1125899906842679);
+        assert_eq!(p[33], 1125899906843617);
+    }
+}